@@ -0,0 +1,403 @@
+use libp2p::Multiaddr;
+use log::{error, info, warn};
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    validate::Validator, CompletionType, Config, Context, Editor, Helper,
+};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+use tokio::sync::mpsc;
+
+// 노드를 띄운 터미널에서 받는 명령들입니다. 명령마다 영어 이름과 한국어 이름이 있고, 예전 이름
+// (show peer, new block 처럼 두 단어로 된 것)도 그대로 받습니다.
+//
+//   > help 잔액
+//   > tx 3f2a... 10 1
+//   > mine "첫 번째 블록"
+//
+// 인자는 공백으로 나누며, 공백이 들어간 인자는 '...' 나 "..." 로 감쌉니다. "..." 안과 따옴표 밖에서는
+// \ 뒤의 글자를 그대로 씁니다. Tab 으로 명령 이름을 완성하고, 위아래 화살표로 이전 명령을 다시 부릅니다.
+// 데이터 디렉토리가 있으면 명령 기록을 그 안의 history 파일에 남겨 다음 실행에도 씁니다.
+// Ctrl-C 나 quit 은 노드를 끄고, 표준 입력이 닫히면(Ctrl-D) 명령만 더 받지 않고 노드는 계속 돕니다.
+
+const 기록_파일_이름: &str = "history";
+const 기록_최대_개수: usize = 1000;
+const 프롬프트: &str = "> ";
+
+struct 명령_정의 {
+    // 첫 이름이 대표 이름이고 도움말 목록에 씁니다.
+    이름들: &'static [&'static str],
+    인자: &'static str,
+    설명: &'static str,
+}
+
+const 명령들: &[명령_정의] = &[
+    명령_정의 {
+        이름들: &["help", "도움말", "?"],
+        인자: "[명령]",
+        설명: "명령 목록을 보이거나, 명령을 주면 그 명령의 사용법을 보입니다.",
+    },
+    명령_정의 {
+        이름들: &["peers", "피어", "show peer"],
+        인자: "",
+        설명: "mDNS 와 Kademlia 로 찾은 피어들과 gossipsub 점수를 보입니다.",
+    },
+    명령_정의 {
+        이름들: &["chain", "체인", "show chain"],
+        인자: "",
+        설명: "주 체인의 블록들을 JSON 으로 보입니다.",
+    },
+    명령_정의 {
+        이름들: &["miner", "채굴기", "show miner"],
+        인자: "",
+        설명: "채굴 중인지와 해시레이트를 보입니다.",
+    },
+    명령_정의 {
+        이름들: &["address", "주소", "show address"],
+        인자: "",
+        설명: "이 노드의 주소를 보입니다. 채굴 보상을 받고 tx 로 보낼 때 송신자가 되는 주소입니다.",
+    },
+    명령_정의 {
+        이름들: &["balance", "잔액", "show balance"],
+        인자: "[주소]",
+        설명: "주소의 잔액과 다음 논스를 보입니다. 주소를 생략하면 이 노드의 주소입니다.",
+    },
+    명령_정의 {
+        이름들: &["mempool", "거래풀", "show mempool"],
+        인자: "",
+        설명: "거래 풀의 트랜잭션들을 수수료가 높은 순서로 보입니다.",
+    },
+    명령_정의 {
+        이름들: &["proof", "증명", "show proof"],
+        인자: "<블록 id> <항목 번호>",
+        설명: "블록 항목의 머클 증명을 보입니다. 0번 항목은 데이터, 1번부터는 트랜잭션입니다.",
+    },
+    명령_정의 {
+        이름들: &["mine", "채굴", "new block"],
+        인자: "[데이터...]",
+        설명: "데이터와 거래 풀의 트랜잭션들을 담은 블록을 채굴합니다. 인자가 여럿이면 공백으로 이어 붙입니다.",
+    },
+    명령_정의 {
+        이름들: &["tx", "송금", "new tx"],
+        인자: "<수신자 주소> <금액> [수수료]",
+        설명: "노드 키로 서명한 트랜잭션을 거래 풀에 넣고 다른 노드들에게 퍼뜨립니다.",
+    },
+    명령_정의 {
+        이름들: &["dial", "연결"],
+        인자: "<multiaddr>",
+        설명: "주소로 연결합니다. 끝에 /p2p/<피어 ID> 가 있으면 Kademlia 에도 넣습니다.",
+    },
+    명령_정의 {
+        이름들: &["quit", "종료", "exit"],
+        인자: "",
+        설명: "노드를 끕니다.",
+    },
+];
+
+#[derive(Debug)]
+pub enum 명령 {
+    도움말(Option<&'static str>),
+    피어,
+    체인,
+    채굴기,
+    주소,
+    잔액(Option<String>),
+    거래_풀,
+    증명 { 블록_id: u64, 인덱스: usize },
+    채굴(String),
+    송금 { 수신자: String, 금액: u64, 수수료: u64 },
+    연결(Multiaddr),
+    종료,
+}
+
+#[derive(Debug)]
+pub enum 명령_오류 {
+    닫히지_않은_따옴표,
+    모르는_명령(String),
+    잘못된_인자 { 명령: &'static str, 사유: String },
+}
+
+impl fmt::Display for 명령_오류 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            명령_오류::닫히지_않은_따옴표 => write!(f, "따옴표가 닫히지 않았습니다"),
+            명령_오류::모르는_명령(이름) => write!(f, "모르는 명령: {} (help 로 명령 목록을 봅니다)", 이름),
+            명령_오류::잘못된_인자 { 명령, 사유 } => {
+                write!(f, "{}\n사용법: {}", 사유, 사용법(정의_찾기(명령).expect("대표 이름으로 찾음")))
+            }
+        }
+    }
+}
+
+fn 사용법(정의: &명령_정의) -> String {
+    match 정의.인자 {
+        "" => 정의.이름들[0].to_string(),
+        인자 => format!("{} {}", 정의.이름들[0], 인자),
+    }
+}
+
+fn 정의_찾기(대표_이름: &str) -> Option<&'static 명령_정의> {
+    명령들.iter().find(|정의| 정의.이름들[0] == 대표_이름)
+}
+
+// 데이터 디렉토리가 없으면 명령 기록은 메모리에만 둡니다.
+pub fn 기록_경로(데이터_디렉토리: Option<&Path>) -> Option<PathBuf> {
+    데이터_디렉토리.map(|디렉토리| 디렉토리.join(기록_파일_이름))
+}
+
+// 줄을 인자들로 나눕니다. 작은따옴표 안은 그대로 두고, 큰따옴표 안과 따옴표 밖에서는 \ 로 다음 글자를 이스케이프합니다.
+fn 단어_나누기(줄: &str) -> Result<Vec<String>, 명령_오류> {
+    let mut 단어들 = vec![];
+    let mut 단어: Option<String> = None;
+    let mut 따옴표: Option<char> = None;
+    let mut 글자들 = 줄.chars();
+    while let Some(글자) = 글자들.next() {
+        match (따옴표, 글자) {
+            (Some('\''), '\'') | (Some('"'), '"') => 따옴표 = None,
+            (Some('\''), _) => 단어.get_or_insert_with(String::new).push(글자),
+            (_, '\\') => {
+                // 줄 끝의 \ 는 그대로 둡니다.
+                let 다음 = 글자들.next().unwrap_or('\\');
+                단어.get_or_insert_with(String::new).push(다음);
+            }
+            (Some(_), _) => 단어.get_or_insert_with(String::new).push(글자),
+            (None, '\'') | (None, '"') => {
+                따옴표 = Some(글자);
+                단어.get_or_insert_with(String::new);
+            }
+            (None, _) if 글자.is_whitespace() => 단어들.extend(단어.take()),
+            (None, _) => 단어.get_or_insert_with(String::new).push(글자),
+        }
+    }
+    if 따옴표.is_some() {
+        return Err(명령_오류::닫히지_않은_따옴표);
+    }
+    단어들.extend(단어);
+    Ok(단어들)
+}
+
+// 이름이 가장 많은 단어와 맞는 명령을 고릅니다. show 처럼 예전 이름의 앞부분만 맞으면 찾지 못합니다.
+fn 명령_고르기<'a>(단어들: &'a [String]) -> Option<(&'static 명령_정의, &'a [String])> {
+    명령들
+        .iter()
+        .flat_map(|정의| 정의.이름들.iter().map(move |이름| (정의, 이름)))
+        .filter_map(|(정의, 이름)| {
+            let 이름_단어들: Vec<&str> = 이름.split(' ').collect();
+            let 맞음 = 단어들.len() >= 이름_단어들.len()
+                && 이름_단어들.iter().zip(단어들).all(|(이름, 단어)| 이름.eq_ignore_ascii_case(단어));
+            if 맞음 {
+                Some((정의, 이름_단어들.len()))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|(_, 길이)| *길이)
+        .map(|(정의, 길이)| (정의, &단어들[길이..]))
+}
+
+// 한 줄을 명령으로 바꿉니다. 빈 줄이면 None 입니다.
+pub fn 해석(줄: &str) -> Result<Option<명령>, 명령_오류> {
+    let 단어들 = 단어_나누기(줄)?;
+    if 단어들.is_empty() {
+        return Ok(None);
+    }
+    let (정의, 인자들) = match 명령_고르기(&단어들) {
+        Some(찾음) => 찾음,
+        None => return Err(명령_오류::모르는_명령(단어들[0].clone())),
+    };
+    let 대표_이름 = 정의.이름들[0];
+    let 오류 = |사유: String| 명령_오류::잘못된_인자 { 명령: 대표_이름, 사유 };
+    let 개수_확인 = |최소: usize, 최대: usize| {
+        if 인자들.len() < 최소 || 인자들.len() > 최대 {
+            Err(오류(format!("인자가 {}개입니다", 인자들.len())))
+        } else {
+            Ok(())
+        }
+    };
+    let 정수 = |이름: &str, 값: &str| {
+        값.parse::<u64>()
+            .map_err(|_| 오류(format!("{} 는 0 이상의 정수여야 합니다: {}", 이름, 값)))
+    };
+
+    let 명령 = match 대표_이름 {
+        "help" => match 인자들 {
+            [] => 명령::도움말(None),
+            _ => match 명령_고르기(인자들) {
+                Some((정의, [])) => 명령::도움말(Some(정의.이름들[0])),
+                _ => return Err(명령_오류::모르는_명령(인자들.join(" "))),
+            },
+        },
+        "peers" | "chain" | "miner" | "address" | "mempool" | "quit" => {
+            개수_확인(0, 0)?;
+            match 대표_이름 {
+                "peers" => 명령::피어,
+                "chain" => 명령::체인,
+                "miner" => 명령::채굴기,
+                "address" => 명령::주소,
+                "mempool" => 명령::거래_풀,
+                _ => 명령::종료,
+            }
+        }
+        "balance" => {
+            개수_확인(0, 1)?;
+            명령::잔액(인자들.first().cloned())
+        }
+        "proof" => {
+            개수_확인(2, 2)?;
+            let 블록_id = 정수("블록 id", &인자들[0])?;
+            let 인덱스 = 인자들[1]
+                .parse::<usize>()
+                .map_err(|_| 오류(format!("항목 번호는 0 이상의 정수여야 합니다: {}", 인자들[1])))?;
+            명령::증명 { 블록_id, 인덱스 }
+        }
+        "mine" => 명령::채굴(인자들.join(" ")),
+        "tx" => {
+            개수_확인(2, 3)?;
+            let 금액 = 정수("금액", &인자들[1])?;
+            let 수수료 = match 인자들.get(2) {
+                Some(수수료) => 정수("수수료", 수수료)?,
+                None => 0,
+            };
+            명령::송금 { 수신자: 인자들[0].clone(), 금액, 수수료 }
+        }
+        "dial" => {
+            개수_확인(1, 1)?;
+            let 주소 = 인자들[0]
+                .parse::<Multiaddr>()
+                .map_err(|e| 오류(format!("multiaddr 가 아닙니다: {}", e)))?;
+            명령::연결(주소)
+        }
+        _ => unreachable!("명령들 표의 모든 대표 이름을 처리함"),
+    };
+    Ok(Some(명령))
+}
+
+// 로그 수준과 상관없이 보이도록 표준 출력에 씁니다.
+pub fn 도움말_출력(대표_이름: Option<&str>) {
+    match 대표_이름.and_then(정의_찾기) {
+        Some(정의) => {
+            println!("사용법: {}", 사용법(정의));
+            println!("  {}", 정의.설명);
+            println!("  다른 이름: {}", 정의.이름들[1..].join(", "));
+        }
+        None => {
+            println!("명령들 (help <명령> 으로 자세히 봅니다):");
+            for 정의 in 명령들 {
+                println!("  {}  ({})", 사용법(정의), 정의.이름들[1..].join(", "));
+                println!("      {}", 정의.설명);
+            }
+        }
+    }
+}
+
+// 지금까지 친 단어들 다음에 올 수 있는 이름 조각들입니다. help 뒤에서는 명령 이름을 처음부터 완성합니다.
+fn 완성_후보(앞_단어들: &[&str], 조각: &str) -> Vec<String> {
+    let 앞_단어들 = match 앞_단어들.split_first() {
+        Some((첫, 나머지)) if 정의_찾기("help").map_or(false, |정의| 정의.이름들.contains(첫)) => 나머지,
+        _ => 앞_단어들,
+    };
+    let mut 후보들: Vec<String> = 명령들
+        .iter()
+        .flat_map(|정의| 정의.이름들.iter())
+        .filter_map(|이름| {
+            let 이름_단어들: Vec<&str> = 이름.split(' ').collect();
+            let 앞이_맞음 = 이름_단어들.len() > 앞_단어들.len()
+                && 이름_단어들.iter().zip(앞_단어들).all(|(이름, 단어)| 이름.eq_ignore_ascii_case(단어));
+            match 이름_단어들.get(앞_단어들.len()) {
+                Some(다음) if 앞이_맞음 && 다음.starts_with(조각) => Some(다음.to_string()),
+                _ => None,
+            }
+        })
+        .collect();
+    후보들.sort();
+    후보들.dedup();
+    후보들
+}
+
+struct 완성기;
+
+impl Completer for 완성기 {
+    type Candidate = String;
+
+    fn complete(&self, 줄: &str, 위치: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let 앞 = &줄[..위치];
+        let 시작 = 앞
+            .char_indices()
+            .rev()
+            .find(|(_, 글자)| 글자.is_whitespace())
+            .map_or(0, |(i, 글자)| i + 글자.len_utf8());
+        let 앞_단어들: Vec<&str> = 앞[..시작].split_whitespace().collect();
+        Ok((시작, 완성_후보(&앞_단어들, &앞[시작..])))
+    }
+}
+
+impl Hinter for 완성기 {
+    type Hint = String;
+}
+
+impl Highlighter for 완성기 {}
+
+impl Validator for 완성기 {}
+
+impl Helper for 완성기 {}
+
+// 줄 편집기는 입력을 기다리는 동안 스레드를 막으므로 전용 스레드에서 돌리고, 해석한 명령만 메인 루프로 보냅니다.
+pub fn 입력_시작(기록_파일: Option<PathBuf>) -> mpsc::UnboundedReceiver<명령> {
+    let (송신자, 수신) = mpsc::unbounded_channel();
+    std::thread::spawn(move || 입력_반복(기록_파일, 송신자));
+    수신
+}
+
+fn 입력_반복(기록_파일: Option<PathBuf>, 송신자: mpsc::UnboundedSender<명령>) {
+    let 설정 = Config::builder()
+        .max_history_size(기록_최대_개수)
+        .history_ignore_dups(true)
+        .history_ignore_space(true)
+        .completion_type(CompletionType::List)
+        .build();
+    let mut 편집기 = Editor::<완성기>::with_config(설정);
+    편집기.set_helper(Some(완성기));
+    if let Some(경로) = &기록_파일 {
+        // 처음 실행할 때는 파일이 없습니다.
+        let _ = 편집기.load_history(경로);
+    }
+
+    loop {
+        let 줄 = match 편집기.readline(프롬프트) {
+            Ok(줄) => 줄,
+            Err(ReadlineError::Interrupted) => {
+                let _ = 송신자.send(명령::종료);
+                return;
+            }
+            Err(ReadlineError::Eof) => {
+                info!("표준 입력이 닫혀 더 이상 명령을 받지 않습니다");
+                return;
+            }
+            Err(e) => {
+                error!("명령을 읽을 수 없습니다: {}", e);
+                return;
+            }
+        };
+        if !줄.trim().is_empty() {
+            편집기.add_history_entry(줄.as_str());
+            if let Some(경로) = &기록_파일 {
+                if let Err(e) = 편집기.save_history(경로) {
+                    warn!("명령 기록을 {} 에 쓸 수 없습니다: {}", 경로.display(), e);
+                }
+            }
+        }
+        match 해석(&줄) {
+            Ok(Some(명령)) => {
+                // 종료 뒤에 다시 readline 에 들어가면 터미널이 raw 모드로 남으므로 여기서 멈춥니다.
+                let 끝 = matches!(명령, 명령::종료);
+                if 송신자.send(명령).is_err() || 끝 {
+                    return;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => error!("{}", e),
+        }
+    }
+}
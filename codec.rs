@@ -0,0 +1,396 @@
+use super::{블록, 블록_헤더};
+use crate::transaction::트랜잭션;
+use std::fmt;
+
+// 블록 헤더 맨 앞에 붙는 인코딩 버전입니다. 해시에도 포함되므로 배치를 바꾸려면 이 값을 올리고,
+// 읽을 때는 이전 버전도 계속 받아들여야 서로 다른 버전의 노드가 같은 체인을 검증할 수 있습니다.
+// 지금은 버전 1 만 있고, 모르는 버전은 지원하지_않는_버전 오류로 거부합니다.
+pub const 인코딩_버전: u8 = 1;
+
+// 배치 (모든 정수는 big-endian, 문자열과 목록은 u32 길이 접두사)
+//   헤더     = 버전 u8 | id u64 | 타임스탬프 i64 | 이전_해시 | 머클_루트 | 채굴자 | 난이도_비트 u32 | 논스 u64
+//   블록     = 헤더 | 해시 | 데이터 | 트랜잭션 개수 u32 | 트랜잭션...
+//   트랜잭션 = 송신자 | 수신자 | 금액 u64 | 논스 u64 | 수수료 u64 | 서명
+// 논스를 헤더 끝에 두어 채굴할 때 마지막 8바이트만 바뀌게 했습니다.
+//
+// 예) id 1, 타임스탬프 0, 문자열은 모두 빈 값, 난이도_비트 0x207fffff, 논스 0 인 헤더는
+//   01 0000000000000001 0000000000000000 00000000 00000000 00000000 207fffff 0000000000000000
+// 의 41바이트이고, 이 바이트열을 다시 읽으면 같은 헤더가 나와야 합니다.
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum 디코딩_오류 {
+    바이트_부족 { 필요: usize, 남음: usize },
+    지원하지_않는_버전(u8),
+    알_수_없는_종류(u8),
+    잘못된_문자열,
+    남은_바이트(usize),
+}
+
+impl fmt::Display for 디코딩_오류 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            디코딩_오류::바이트_부족 { 필요, 남음 } => {
+                write!(f, "{} 바이트가 필요한데 {} 바이트만 남았습니다", 필요, 남음)
+            }
+            디코딩_오류::지원하지_않는_버전(버전) => write!(f, "지원하지 않는 인코딩 버전 {} 입니다", 버전),
+            디코딩_오류::알_수_없는_종류(종류) => write!(f, "알 수 없는 종류 번호 {} 입니다", 종류),
+            디코딩_오류::잘못된_문자열 => write!(f, "문자열이 utf-8 이 아닙니다"),
+            디코딩_오류::남은_바이트(개수) => write!(f, "끝에 읽지 않은 {} 바이트가 남았습니다", 개수),
+        }
+    }
+}
+
+// 읽는 중인 바이트열의 남은 부분입니다.
+pub struct 읽기_커서<'a> {
+    남은: &'a [u8],
+}
+
+impl<'a> 읽기_커서<'a> {
+    pub fn new(바이트: &'a [u8]) -> Self {
+        Self { 남은: 바이트 }
+    }
+
+    pub fn 남은_길이(&self) -> usize {
+        self.남은.len()
+    }
+
+    fn 바이트들(&mut self, 개수: usize) -> Result<&'a [u8], 디코딩_오류> {
+        if self.남은.len() < 개수 {
+            return Err(디코딩_오류::바이트_부족 {
+                필요: 개수,
+                남음: self.남은.len(),
+            });
+        }
+        let (앞, 뒤) = self.남은.split_at(개수);
+        self.남은 = 뒤;
+        Ok(앞)
+    }
+
+    fn 고정<const N: usize>(&mut self) -> Result<[u8; N], 디코딩_오류> {
+        let mut 배열 = [0u8; N];
+        배열.copy_from_slice(self.바이트들(N)?);
+        Ok(배열)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, 디코딩_오류> {
+        Ok(self.고정::<1>()?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, 디코딩_오류> {
+        Ok(u16::from_be_bytes(self.고정()?))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, 디코딩_오류> {
+        Ok(u32::from_be_bytes(self.고정()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, 디코딩_오류> {
+        Ok(u64::from_be_bytes(self.고정()?))
+    }
+
+    pub fn u128(&mut self) -> Result<u128, 디코딩_오류> {
+        Ok(u128::from_be_bytes(self.고정()?))
+    }
+
+    pub fn i64(&mut self) -> Result<i64, 디코딩_오류> {
+        Ok(i64::from_be_bytes(self.고정()?))
+    }
+
+    // u32 길이 접두사가 붙은 바이트열입니다.
+    pub fn 길이_접두_바이트(&mut self) -> Result<&'a [u8], 디코딩_오류> {
+        let 길이 = self.u32()? as usize;
+        self.바이트들(길이)
+    }
+
+    pub fn 문자열(&mut self) -> Result<String, 디코딩_오류> {
+        let 바이트 = self.길이_접두_바이트()?;
+        String::from_utf8(바이트.to_vec()).map_err(|_| 디코딩_오류::잘못된_문자열)
+    }
+
+    pub fn 목록<T: 이진_인코딩>(&mut self) -> Result<Vec<T>, 디코딩_오류> {
+        let 개수 = self.u32()? as usize;
+        // 개수는 보낸 쪽이 정하므로, 남은 바이트보다 많이 미리 잡지 않습니다.
+        let mut 목록 = Vec::with_capacity(개수.min(self.남은.len()));
+        for _ in 0..개수 {
+            목록.push(T::인코딩_읽기(self)?);
+        }
+        Ok(목록)
+    }
+}
+
+pub fn 길이_접두_바이트_쓰기(출력: &mut Vec<u8>, 바이트: &[u8]) {
+    출력.extend_from_slice(&(바이트.len() as u32).to_be_bytes());
+    출력.extend_from_slice(바이트);
+}
+
+pub fn 문자열_쓰기(출력: &mut Vec<u8>, 문자열: &str) {
+    길이_접두_바이트_쓰기(출력, 문자열.as_bytes());
+}
+
+pub fn 목록_쓰기<T: 이진_인코딩>(출력: &mut Vec<u8>, 목록: &[T]) {
+    출력.extend_from_slice(&(목록.len() as u32).to_be_bytes());
+    for 항목 in 목록 {
+        항목.인코딩_쓰기(출력);
+    }
+}
+
+// 해시와 네트워크 메시지에 쓰는 정해진 이진 표현입니다. 같은 값은 항상 같은 바이트열이 됩니다.
+// json 은 사람이 읽는 출력(show chain 등)에만 씁니다.
+pub trait 이진_인코딩: Sized {
+    fn 인코딩_쓰기(&self, 출력: &mut Vec<u8>);
+    fn 인코딩_읽기(입력: &mut 읽기_커서) -> Result<Self, 디코딩_오류>;
+
+    fn 인코딩(&self) -> Vec<u8> {
+        let mut 출력 = vec![];
+        self.인코딩_쓰기(&mut 출력);
+        출력
+    }
+
+    // 끝에 남는 바이트가 있으면 거부해 한 값이 두 가지 바이트열로 받아들여지지 않게 합니다.
+    fn 디코딩(바이트: &[u8]) -> Result<Self, 디코딩_오류> {
+        let mut 입력 = 읽기_커서::new(바이트);
+        let 값 = Self::인코딩_읽기(&mut 입력)?;
+        match 입력.남은_길이() {
+            0 => Ok(값),
+            남음 => Err(디코딩_오류::남은_바이트(남음)),
+        }
+    }
+}
+
+impl 이진_인코딩 for String {
+    fn 인코딩_쓰기(&self, 출력: &mut Vec<u8>) {
+        문자열_쓰기(출력, self);
+    }
+
+    fn 인코딩_읽기(입력: &mut 읽기_커서) -> Result<Self, 디코딩_오류> {
+        입력.문자열()
+    }
+}
+
+impl 이진_인코딩 for 블록_헤더 {
+    fn 인코딩_쓰기(&self, 출력: &mut Vec<u8>) {
+        출력.push(인코딩_버전);
+        출력.extend_from_slice(&self.id.to_be_bytes());
+        출력.extend_from_slice(&self.타임스탬프.to_be_bytes());
+        문자열_쓰기(출력, &self.이전_해시);
+        문자열_쓰기(출력, &self.머클_루트);
+        문자열_쓰기(출력, &self.채굴자);
+        출력.extend_from_slice(&self.난이도_비트.to_be_bytes());
+        출력.extend_from_slice(&self.논스.to_be_bytes());
+    }
+
+    fn 인코딩_읽기(입력: &mut 읽기_커서) -> Result<Self, 디코딩_오류> {
+        let 버전 = 입력.u8()?;
+        if 버전 != 인코딩_버전 {
+            return Err(디코딩_오류::지원하지_않는_버전(버전));
+        }
+        Ok(블록_헤더 {
+            id: 입력.u64()?,
+            타임스탬프: 입력.i64()?,
+            이전_해시: 입력.문자열()?,
+            머클_루트: 입력.문자열()?,
+            채굴자: 입력.문자열()?,
+            난이도_비트: 입력.u32()?,
+            논스: 입력.u64()?,
+        })
+    }
+}
+
+impl 이진_인코딩 for 블록 {
+    fn 인코딩_쓰기(&self, 출력: &mut Vec<u8>) {
+        self.헤더().인코딩_쓰기(출력);
+        문자열_쓰기(출력, &self.해시);
+        문자열_쓰기(출력, &self.데이터);
+        목록_쓰기(출력, &self.트랜잭션들);
+    }
+
+    fn 인코딩_읽기(입력: &mut 읽기_커서) -> Result<Self, 디코딩_오류> {
+        let 헤더 = 블록_헤더::인코딩_읽기(입력)?;
+        let 해시 = 입력.문자열()?;
+        let 데이터 = 입력.문자열()?;
+        let 트랜잭션들 = 입력.목록()?;
+        Ok(블록::조립(헤더, 해시, 데이터, 트랜잭션들))
+    }
+}
+
+impl 이진_인코딩 for 트랜잭션 {
+    fn 인코딩_쓰기(&self, 출력: &mut Vec<u8>) {
+        문자열_쓰기(출력, &self.송신자);
+        문자열_쓰기(출력, &self.수신자);
+        출력.extend_from_slice(&self.금액.to_be_bytes());
+        출력.extend_from_slice(&self.논스.to_be_bytes());
+        출력.extend_from_slice(&self.수수료.to_be_bytes());
+        문자열_쓰기(출력, &self.서명);
+    }
+
+    fn 인코딩_읽기(입력: &mut 읽기_커서) -> Result<Self, 디코딩_오류> {
+        Ok(트랜잭션 {
+            송신자: 입력.문자열()?,
+            수신자: 입력.문자열()?,
+            금액: 입력.u64()?,
+            논스: 입력.u64()?,
+            수수료: 입력.u64()?,
+            서명: 입력.문자열()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{동기화_요청, 메시지, 프로토콜_버전, 프로토콜_오류};
+
+    // 위 배치 설명의 예시 헤더입니다.
+    fn 예시_헤더() -> 블록_헤더 {
+        블록_헤더 {
+            id: 1,
+            타임스탬프: 0,
+            이전_해시: String::new(),
+            머클_루트: String::new(),
+            채굴자: String::new(),
+            난이도_비트: 0x207fffff,
+            논스: 0,
+        }
+    }
+
+    fn 예시_트랜잭션() -> 트랜잭션 {
+        트랜잭션 {
+            송신자: "a".repeat(64),
+            수신자: "b".repeat(64),
+            금액: 10,
+            논스: 3,
+            수수료: 1,
+            서명: "c".repeat(128),
+        }
+    }
+
+    fn 예시_블록() -> 블록 {
+        let 데이터 = "첫 블록".to_string();
+        let 트랜잭션들 = vec![예시_트랜잭션()];
+        let 헤더 = 블록_헤더 {
+            이전_해시: "0".repeat(64),
+            머클_루트: 블록::머클_루트_계산(&데이터, &트랜잭션들),
+            채굴자: "a".repeat(64),
+            논스: 42,
+            ..예시_헤더()
+        };
+        let 해시 = hex::encode(헤더.해시());
+        블록::조립(헤더, 해시, 데이터, 트랜잭션들)
+    }
+
+    #[test]
+    fn 헤더는_정해진_바이트열과_해시를_가짐() {
+        let 바이트 = hex::decode(
+            "0100000000000000010000000000000000000000000000000000000000207fffff0000000000000000",
+        )
+        .expect("16진수");
+        assert_eq!(예시_헤더().인코딩(), 바이트);
+        assert_eq!(블록_헤더::디코딩(&바이트), Ok(예시_헤더()));
+        assert_eq!(
+            hex::encode(예시_헤더().해시()),
+            "92bbe2c65ea35d76a7c9c621706ef1d0dee64a84fc735eec32c02c38a51a68da"
+        );
+    }
+
+    #[test]
+    fn 왕복() {
+        let 트랜잭션 = 예시_트랜잭션();
+        assert_eq!(트랜잭션::디코딩(&트랜잭션.인코딩()), Ok(트랜잭션));
+
+        let block = 예시_블록();
+        assert_eq!(블록_헤더::디코딩(&block.헤더().인코딩()), Ok(block.헤더()));
+        assert_eq!(블록::디코딩(&block.인코딩()), Ok(block));
+    }
+
+    #[test]
+    fn 모르는_헤더_버전은_거부() {
+        let mut 바이트 = 예시_블록().인코딩();
+        바이트[0] = 인코딩_버전 + 1;
+        assert_eq!(블록::디코딩(&바이트), Err(디코딩_오류::지원하지_않는_버전(인코딩_버전 + 1)));
+        assert_eq!(
+            블록_헤더::디코딩(&바이트[..41]),
+            Err(디코딩_오류::지원하지_않는_버전(인코딩_버전 + 1))
+        );
+    }
+
+    #[test]
+    fn 모자라거나_남는_바이트는_거부() {
+        let mut 바이트 = 예시_트랜잭션().인코딩();
+        assert!(matches!(
+            트랜잭션::디코딩(&바이트[..바이트.len() - 1]),
+            Err(디코딩_오류::바이트_부족 { .. })
+        ));
+        바이트.push(0);
+        assert_eq!(트랜잭션::디코딩(&바이트), Err(디코딩_오류::남은_바이트(1)));
+    }
+
+    #[test]
+    fn 봉투_왕복() {
+        let block = 예시_블록();
+        match 메시지::봉투_해석(&메시지::새_블록(block.clone()).봉투_인코딩()) {
+            Ok((버전, 메시지::새_블록(받은))) => {
+                assert_eq!(버전, 프로토콜_버전);
+                assert_eq!(받은, block);
+            }
+            다른 => panic!("새_블록이 아님: {:?}", 다른),
+        }
+
+        let 트랜잭션 = 예시_트랜잭션();
+        match 메시지::봉투_해석(&메시지::새_트랜잭션(트랜잭션.clone()).봉투_인코딩()) {
+            Ok((_, 메시지::새_트랜잭션(받은))) => assert_eq!(받은, 트랜잭션),
+            다른 => panic!("새_트랜잭션이 아님: {:?}", 다른),
+        }
+    }
+
+    // 봉투 = 버전 | ID | 본문 길이 | 본문
+    fn 봉투(버전: u16, id: u8, 본문: &[u8]) -> Vec<u8> {
+        let mut 출력 = 버전.to_be_bytes().to_vec();
+        출력.push(id);
+        길이_접두_바이트_쓰기(&mut 출력, 본문);
+        출력
+    }
+
+    #[test]
+    fn 봉투_버전_호환() {
+        let 본문 = 예시_트랜잭션().인코딩();
+
+        // 더 새로운 버전이 본문 끝에 덧붙인 필드는 읽지 않고 넘깁니다.
+        let mut 늘어난_본문 = 본문.clone();
+        늘어난_본문.extend_from_slice(&[1, 2, 3]);
+        match 메시지::봉투_해석(&봉투(프로토콜_버전 + 1, 2, &늘어난_본문)) {
+            Ok((버전, 메시지::새_트랜잭션(받은))) => {
+                assert_eq!(버전, 프로토콜_버전 + 1);
+                assert_eq!(받은, 예시_트랜잭션());
+            }
+            다른 => panic!("새_트랜잭션이 아님: {:?}", 다른),
+        }
+        // 같은 버전이면 남는 바이트는 오류입니다.
+        assert_eq!(
+            메시지::봉투_해석(&봉투(프로토콜_버전, 2, &늘어난_본문)).err(),
+            Some(프로토콜_오류::디코딩(디코딩_오류::남은_바이트(3)))
+        );
+        assert_eq!(
+            메시지::봉투_해석(&봉투(0, 2, &본문)).err(),
+            Some(프로토콜_오류::호환되지_않는_버전(0))
+        );
+    }
+
+    #[test]
+    fn 모르는_종류는_거부() {
+        assert_eq!(
+            메시지::봉투_해석(&봉투(프로토콜_버전, 99, &[])).err(),
+            Some(프로토콜_오류::알_수_없는_메시지 { 버전: 프로토콜_버전, id: 99 })
+        );
+        assert!(matches!(
+            동기화_요청::디코딩(&[99]),
+            Err(디코딩_오류::알_수_없는_종류(99))
+        ));
+
+        let mut 블록_본문 = 예시_블록().인코딩();
+        블록_본문[0] = 인코딩_버전 + 1;
+        assert_eq!(
+            메시지::봉투_해석(&봉투(프로토콜_버전, 1, &블록_본문)).err(),
+            Some(프로토콜_오류::디코딩(디코딩_오류::지원하지_않는_버전(인코딩_버전 + 1)))
+        );
+    }
+}
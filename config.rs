@@ -0,0 +1,381 @@
+use crate::{difficulty::난이도_설정, keyfile, target::목표값};
+use libp2p::Multiaddr;
+use serde::Deserialize;
+use std::{
+    fmt, fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+// 노드 설정은 기본값 < 설정 파일 < 환경 변수 < 명령줄 인자 순으로 덮어씁니다.
+//
+// 설정 파일 (--config 또는 NODE_CONFIG, 모든 항목은 생략 가능)
+//   listen = ["/ip4/0.0.0.0/tcp/4001"]
+//   bootstrap = ["/ip4/10.0.0.5/tcp/4001/p2p/12D3KooW..."]
+//   data_dir = "data"
+//   key_file = "data/node.key"
+//   network = "main"
+//   genesis = "genesis.toml"
+//   rpc_listen = "127.0.0.1:8545"
+//   ws_listen = "127.0.0.1:8546"
+//   explorer_listen = "127.0.0.1:8547"
+//   log = "info"
+//   init_delay_secs = 1
+//   [difficulty]
+//   initial_bits = 0x2000ffff
+//   max_target_bits = 0x207fffff
+//   retarget_interval = 10
+//   target_block_time_secs = 10
+//
+// network 는 genesis 명세 파일이 없을 때 쓰는 기본 명세의 이름입니다. 명세 파일이 있으면 그 안의 network 를 씁니다.
+//
+// [difficulty] 도 기본 명세에만 들어갑니다. 난이도 규칙은 블록 검증에 쓰는 합의 규칙이라 체인 ID 에 포함되므로,
+// 값을 바꾸면 다른 체인이 됩니다. 명세 파일을 쓸 때는 명세에 적어야 하고, 여기에 주면 오류입니다.
+//
+// rpc_listen, ws_listen, explorer_listen 을 주지 않으면 JSON-RPC 서버와 WebSocket 구독 서버, 블록 탐색기 API 를 띄우지 않습니다.
+//
+// 환경 변수: NODE_LISTEN, BOOTSTRAP_PEERS (쉼표 구분), CHAIN_DATA_DIR, NODE_KEY_FILE, CHAIN_NETWORK, CHAIN_GENESIS,
+//   NODE_RPC_LISTEN, NODE_WS_LISTEN, NODE_EXPLORER_LISTEN, RUST_LOG
+// 명령줄 인자: --listen, --bootstrap (여러 번 줄 수 있음), --data-dir, --key-file, --network, --genesis,
+//   --rpc-listen, --ws-listen, --explorer-listen, --log,
+//   --init-delay, --initial-bits, --max-target-bits, --retarget-interval, --target-block-time
+pub struct 노드_설정 {
+    pub 수신_주소들: Vec<Multiaddr>,
+    pub 부트스트랩_주소들: Vec<Multiaddr>,
+    // 없으면 블록을 메모리에만 둡니다.
+    pub 데이터_디렉토리: Option<PathBuf>,
+    pub 키_파일: PathBuf,
+    pub 네트워크: String,
+    // 제네시스 명세 파일. 없으면 네트워크 이름과 초기 난이도로 기본 명세를 만듭니다.
+    pub 제네시스_파일: Option<PathBuf>,
+    // JSON-RPC 를 받을 HTTP 주소. 인증이 없으므로 보통 127.0.0.1 에 둡니다.
+    pub rpc_주소: Option<SocketAddr>,
+    // WebSocket 구독을 받을 주소
+    pub ws_주소: Option<SocketAddr>,
+    // 블록 탐색기 REST API 를 받을 HTTP 주소
+    pub explorer_주소: Option<SocketAddr>,
+    // 제네시스 명세 파일이 없을 때 기본 명세에 넣을 난이도 규칙
+    pub 난이도: 난이도_설정,
+    pub 로그: String,
+    // 시작한 뒤 피어를 찾을 시간을 주고 Init 이벤트를 보내기까지 기다리는 시간
+    pub 초기화_지연: Duration,
+}
+
+#[derive(Debug)]
+pub enum 설정_오류 {
+    파일_읽기 { 경로: PathBuf, 오류: io::Error },
+    파일_형식 { 경로: PathBuf, 오류: String },
+    잘못된_값 { 항목: &'static str, 값: String, 사유: String },
+    인자_값_없음(String),
+    모르는_인자(String),
+}
+
+impl fmt::Display for 설정_오류 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            설정_오류::파일_읽기 { 경로, 오류 } => {
+                write!(f, "설정 파일 {} 을 읽을 수 없습니다: {}", 경로.display(), 오류)
+            }
+            설정_오류::파일_형식 { 경로, 오류 } => {
+                write!(f, "설정 파일 {} 의 형식이 잘못되었습니다: {}", 경로.display(), 오류)
+            }
+            설정_오류::잘못된_값 { 항목, 값, 사유 } => write!(f, "{} 의 값 {:?} 이 잘못되었습니다: {}", 항목, 값, 사유),
+            설정_오류::인자_값_없음(인자) => write!(f, "{} 뒤에 값이 필요합니다", 인자),
+            설정_오류::모르는_인자(인자) => write!(f, "알 수 없는 인자 {} 입니다", 인자),
+        }
+    }
+}
+
+// 각 출처에서 읽은 값입니다. 주어지지 않은 항목은 None 이고, 나중 출처의 Some 이 앞의 값을 덮어씁니다.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct 설정_값들 {
+    #[serde(rename = "listen")]
+    수신_주소들: Option<Vec<String>>,
+    #[serde(rename = "bootstrap")]
+    부트스트랩_주소들: Option<Vec<String>>,
+    #[serde(rename = "data_dir")]
+    데이터_디렉토리: Option<PathBuf>,
+    #[serde(rename = "key_file")]
+    키_파일: Option<PathBuf>,
+    #[serde(rename = "network")]
+    네트워크: Option<String>,
+    #[serde(rename = "genesis")]
+    제네시스_파일: Option<PathBuf>,
+    #[serde(rename = "rpc_listen")]
+    rpc_주소: Option<String>,
+    #[serde(rename = "ws_listen")]
+    ws_주소: Option<String>,
+    #[serde(rename = "explorer_listen")]
+    explorer_주소: Option<String>,
+    #[serde(rename = "log")]
+    로그: Option<String>,
+    #[serde(rename = "init_delay_secs")]
+    초기화_지연_초: Option<u64>,
+    #[serde(rename = "difficulty")]
+    난이도: 난이도_값들,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct 난이도_값들 {
+    #[serde(rename = "initial_bits")]
+    초기_난이도_비트: Option<u32>,
+    #[serde(rename = "max_target_bits")]
+    최대_목표_비트: Option<u32>,
+    #[serde(rename = "retarget_interval")]
+    조정_주기: Option<u64>,
+    #[serde(rename = "target_block_time_secs")]
+    목표_블록_시간: Option<i64>,
+}
+
+impl 난이도_값들 {
+    fn 주어짐(&self) -> bool {
+        self.초기_난이도_비트.is_some()
+            || self.최대_목표_비트.is_some()
+            || self.조정_주기.is_some()
+            || self.목표_블록_시간.is_some()
+    }
+}
+
+impl 설정_값들 {
+    fn 덮어쓰기(&mut self, 위: 설정_값들) {
+        self.수신_주소들 = 위.수신_주소들.or_else(|| self.수신_주소들.take());
+        self.부트스트랩_주소들 = 위.부트스트랩_주소들.or_else(|| self.부트스트랩_주소들.take());
+        self.데이터_디렉토리 = 위.데이터_디렉토리.or_else(|| self.데이터_디렉토리.take());
+        self.키_파일 = 위.키_파일.or_else(|| self.키_파일.take());
+        self.네트워크 = 위.네트워크.or_else(|| self.네트워크.take());
+        self.제네시스_파일 = 위.제네시스_파일.or_else(|| self.제네시스_파일.take());
+        self.rpc_주소 = 위.rpc_주소.or_else(|| self.rpc_주소.take());
+        self.ws_주소 = 위.ws_주소.or_else(|| self.ws_주소.take());
+        self.explorer_주소 = 위.explorer_주소.or_else(|| self.explorer_주소.take());
+        self.로그 = 위.로그.or_else(|| self.로그.take());
+        self.초기화_지연_초 = 위.초기화_지연_초.or(self.초기화_지연_초);
+        self.난이도.초기_난이도_비트 = 위.난이도.초기_난이도_비트.or(self.난이도.초기_난이도_비트);
+        self.난이도.최대_목표_비트 = 위.난이도.최대_목표_비트.or(self.난이도.최대_목표_비트);
+        self.난이도.조정_주기 = 위.난이도.조정_주기.or(self.난이도.조정_주기);
+        self.난이도.목표_블록_시간 = 위.난이도.목표_블록_시간.or(self.난이도.목표_블록_시간);
+    }
+
+    fn 파일에서(경로: &Path) -> Result<Self, 설정_오류> {
+        let 내용 = fs::read_to_string(경로).map_err(|오류| 설정_오류::파일_읽기 {
+            경로: 경로.to_path_buf(),
+            오류,
+        })?;
+        toml::from_str(&내용).map_err(|e| 설정_오류::파일_형식 {
+            경로: 경로.to_path_buf(),
+            오류: e.to_string(),
+        })
+    }
+
+    fn 환경_변수에서() -> Self {
+        let 목록 = |이름: &str| {
+            std::env::var(이름).ok().map(|값| {
+                값.split(',')
+                    .map(str::trim)
+                    .filter(|항목| !항목.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+        };
+        설정_값들 {
+            수신_주소들: 목록("NODE_LISTEN"),
+            부트스트랩_주소들: 목록("BOOTSTRAP_PEERS"),
+            데이터_디렉토리: std::env::var("CHAIN_DATA_DIR").ok().map(PathBuf::from),
+            키_파일: std::env::var("NODE_KEY_FILE").ok().map(PathBuf::from),
+            네트워크: std::env::var("CHAIN_NETWORK").ok(),
+            제네시스_파일: std::env::var("CHAIN_GENESIS").ok().map(PathBuf::from),
+            rpc_주소: std::env::var("NODE_RPC_LISTEN").ok(),
+            ws_주소: std::env::var("NODE_WS_LISTEN").ok(),
+            explorer_주소: std::env::var("NODE_EXPLORER_LISTEN").ok(),
+            로그: std::env::var("RUST_LOG").ok(),
+            ..설정_값들::default()
+        }
+    }
+
+    // --config 는 따로 돌려줍니다. 설정 파일을 먼저 읽은 뒤 인자들로 덮어써야 하기 때문입니다.
+    fn 인자에서(인자들: &[String]) -> Result<(Self, Option<PathBuf>), 설정_오류> {
+        let mut 값들 = 설정_값들::default();
+        let mut 설정_파일 = None;
+        let mut 남은 = 인자들.iter();
+        while let Some(인자) = 남은.next() {
+            let mut 값 = || 남은.next().cloned().ok_or_else(|| 설정_오류::인자_값_없음(인자.clone()));
+            match 인자.as_str() {
+                "--config" => 설정_파일 = Some(PathBuf::from(값()?)),
+                "--listen" => 값들.수신_주소들.get_or_insert_with(Vec::new).push(값()?),
+                "--bootstrap" => 값들.부트스트랩_주소들.get_or_insert_with(Vec::new).push(값()?),
+                "--data-dir" => 값들.데이터_디렉토리 = Some(PathBuf::from(값()?)),
+                "--key-file" => 값들.키_파일 = Some(PathBuf::from(값()?)),
+                "--network" => 값들.네트워크 = Some(값()?),
+                "--genesis" => 값들.제네시스_파일 = Some(PathBuf::from(값()?)),
+                "--rpc-listen" => 값들.rpc_주소 = Some(값()?),
+                "--ws-listen" => 값들.ws_주소 = Some(값()?),
+                "--explorer-listen" => 값들.explorer_주소 = Some(값()?),
+                "--log" => 값들.로그 = Some(값()?),
+                "--init-delay" => 값들.초기화_지연_초 = Some(값_해석("--init-delay", &값()?)?),
+                "--initial-bits" => 값들.난이도.초기_난이도_비트 = Some(비트_해석("--initial-bits", &값()?)?),
+                "--max-target-bits" => 값들.난이도.최대_목표_비트 = Some(비트_해석("--max-target-bits", &값()?)?),
+                "--retarget-interval" => {
+                    값들.난이도.조정_주기 = Some(값_해석("--retarget-interval", &값()?)?)
+                }
+                "--target-block-time" => {
+                    값들.난이도.목표_블록_시간 = Some(값_해석("--target-block-time", &값()?)?)
+                }
+                _ => return Err(설정_오류::모르는_인자(인자.clone())),
+            }
+        }
+        Ok((값들, 설정_파일))
+    }
+}
+
+fn 값_해석<T: std::str::FromStr>(항목: &'static str, 값: &str) -> Result<T, 설정_오류>
+where
+    T::Err: fmt::Display,
+{
+    값.parse().map_err(|e: T::Err| 설정_오류::잘못된_값 {
+        항목,
+        값: 값.to_string(),
+        사유: e.to_string(),
+    })
+}
+
+// 난이도 비트는 0x2000ffff 처럼 십육진수로도 받습니다.
+fn 비트_해석(항목: &'static str, 값: &str) -> Result<u32, 설정_오류> {
+    match 값.strip_prefix("0x") {
+        Some(십육진수) => u32::from_str_radix(십육진수, 16).map_err(|e| 설정_오류::잘못된_값 {
+            항목,
+            값: 값.to_string(),
+            사유: e.to_string(),
+        }),
+        None => 값_해석(항목, 값),
+    }
+}
+
+fn 주소들_해석(항목: &'static str, 주소들: Vec<String>) -> Result<Vec<Multiaddr>, 설정_오류> {
+    주소들
+        .into_iter()
+        .map(|주소| {
+            주소.parse().map_err(|e: libp2p::multiaddr::Error| 설정_오류::잘못된_값 {
+                항목,
+                값: 주소,
+                사유: e.to_string(),
+            })
+        })
+        .collect()
+}
+
+impl 노드_설정 {
+    // 명령줄 인자(프로그램 이름과 하위 명령은 뺀 나머지)로 설정을 모아 검증합니다.
+    pub fn 불러오기(인자들: &[String]) -> Result<Self, 설정_오류> {
+        let (인자_값들, 설정_파일) = 설정_값들::인자에서(인자들)?;
+        let mut 값들 = 설정_값들::default();
+        if let Some(경로) = 설정_파일.or_else(|| std::env::var("NODE_CONFIG").ok().map(PathBuf::from)) {
+            값들.덮어쓰기(설정_값들::파일에서(&경로)?);
+        }
+        값들.덮어쓰기(설정_값들::환경_변수에서());
+        값들.덮어쓰기(인자_값들);
+        Self::검증(값들)
+    }
+
+    fn 검증(값들: 설정_값들) -> Result<Self, 설정_오류> {
+        let 수신_주소들 = 주소들_해석(
+            "listen",
+            값들.수신_주소들.unwrap_or_else(|| vec!["/ip4/0.0.0.0/tcp/0".to_string()]),
+        )?;
+        if 수신_주소들.is_empty() {
+            return Err(설정_오류::잘못된_값 {
+                항목: "listen",
+                값: String::new(),
+                사유: "수신 주소가 하나 이상 있어야 합니다".to_string(),
+            });
+        }
+        let 부트스트랩_주소들 = 주소들_해석("bootstrap", 값들.부트스트랩_주소들.unwrap_or_default())?;
+
+        let 네트워크 = 값들.네트워크.unwrap_or_else(|| "main".to_string());
+        if 네트워크.is_empty()
+            || !네트워크
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        {
+            return Err(설정_오류::잘못된_값 {
+                항목: "network",
+                값: 네트워크,
+                사유: "영문 소문자, 숫자, - 와 _ 만 쓸 수 있습니다".to_string(),
+            });
+        }
+
+        if let Some(경로) = 값들.제네시스_파일.as_ref().filter(|_| 값들.난이도.주어짐()) {
+            return Err(설정_오류::잘못된_값 {
+                항목: "difficulty",
+                값: 경로.display().to_string(),
+                사유: "제네시스 명세 파일을 쓸 때는 난이도 규칙을 명세에 적어야 합니다".to_string(),
+            });
+        }
+        let 기본_난이도 = 난이도_설정::default();
+        let 난이도 = 난이도_설정 {
+            초기_난이도_비트: 값들.난이도.초기_난이도_비트.unwrap_or(기본_난이도.초기_난이도_비트),
+            최대_목표_비트: 값들.난이도.최대_목표_비트.unwrap_or(기본_난이도.최대_목표_비트),
+            조정_주기: 값들.난이도.조정_주기.unwrap_or(기본_난이도.조정_주기),
+            목표_블록_시간: 값들.난이도.목표_블록_시간.unwrap_or(기본_난이도.목표_블록_시간),
+        };
+        난이도_검증(&난이도)?;
+
+        let rpc_주소 = 값들
+            .rpc_주소
+            .map(|주소| 값_해석::<SocketAddr>("rpc_listen", &주소))
+            .transpose()?;
+        let ws_주소 = 값들
+            .ws_주소
+            .map(|주소| 값_해석::<SocketAddr>("ws_listen", &주소))
+            .transpose()?;
+        let explorer_주소 = 값들
+            .explorer_주소
+            .map(|주소| 값_해석::<SocketAddr>("explorer_listen", &주소))
+            .transpose()?;
+
+        let 키_파일 = 값들
+            .키_파일
+            .unwrap_or_else(|| keyfile::기본_경로(값들.데이터_디렉토리.as_deref()));
+
+        Ok(노드_설정 {
+            수신_주소들,
+            부트스트랩_주소들,
+            데이터_디렉토리: 값들.데이터_디렉토리,
+            키_파일,
+            네트워크,
+            제네시스_파일: 값들.제네시스_파일,
+            rpc_주소,
+            ws_주소,
+            explorer_주소,
+            난이도,
+            로그: 값들.로그.unwrap_or_else(|| "info".to_string()),
+            초기화_지연: Duration::from_secs(값들.초기화_지연_초.unwrap_or(1)),
+        })
+    }
+}
+
+fn 난이도_검증(난이도: &난이도_설정) -> Result<(), 설정_오류> {
+    let 비트_오류 = |항목, 비트: u32| 설정_오류::잘못된_값 {
+        항목,
+        값: format!("{:#010x}", 비트),
+        사유: "올바른 압축 목표값이 아닙니다".to_string(),
+    };
+    let 초기 = 목표값::압축에서(난이도.초기_난이도_비트).ok_or_else(|| 비트_오류("difficulty.initial_bits", 난이도.초기_난이도_비트))?;
+    let 최대 = 목표값::압축에서(난이도.최대_목표_비트).ok_or_else(|| 비트_오류("difficulty.max_target_bits", 난이도.최대_목표_비트))?;
+    if 초기 > 최대 {
+        return Err(설정_오류::잘못된_값 {
+            항목: "difficulty.initial_bits",
+            값: format!("{:#010x}", 난이도.초기_난이도_비트),
+            사유: "초기 목표값이 max_target_bits 보다 쉽습니다".to_string(),
+        });
+    }
+    if 난이도.목표_블록_시간 <= 0 {
+        return Err(설정_오류::잘못된_값 {
+            항목: "difficulty.target_block_time_secs",
+            값: 난이도.목표_블록_시간.to_string(),
+            사유: "0 보다 커야 합니다".to_string(),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use super::블록;
+use crate::target::목표값;
+
+// 난이도는 블록마다 목표값의 압축 표현(난이도_비트)으로 저장됩니다.
+// 목표값이 절반이 될 때마다 평균 채굴 시간은 두 배가 됩니다.
+#[derive(Debug, Clone)]
+pub struct 난이도_설정 {
+    pub 초기_난이도_비트: u32,
+    // 가장 쉬운 목표값. 재조정이 이보다 큰 목표값을 만들지 않습니다.
+    pub 최대_목표_비트: u32,
+    // 몇 블록마다 난이도를 다시 계산할지
+    pub 조정_주기: u64,
+    // 블록 하나에 걸리기를 바라는 시간(초)
+    pub 목표_블록_시간: i64,
+}
+
+impl Default for 난이도_설정 {
+    fn default() -> Self {
+        Self {
+            // 해시 앞 8비트가 0 이어야 하는 목표값
+            초기_난이도_비트: 0x2000_ffff,
+            최대_목표_비트: 0x207f_ffff,
+            조정_주기: 10,
+            목표_블록_시간: 10,
+        }
+    }
+}
+
+impl 난이도_설정 {
+    pub fn 최대_목표(&self) -> 목표값 {
+        목표값::압축에서(self.최대_목표_비트).expect("최대 목표 비트가 올바른 압축 표현이어야 합니다")
+    }
+}
+
+// 이전_블록들 뒤에 붙을 블록이 가져야 하는 난이도_비트를 계산합니다.
+// 조정 주기에 해당하는 높이가 아니면 직전 블록의 값을 그대로 이어받고,
+// 조정 높이에서는 지난 주기 동안 실제로 걸린 시간과 목표 시간의 비율만큼 목표값을 늘리거나 줄입니다.
+// 한 번에 4배 넘게 바뀌지 않도록 실제 시간을 잘라 냅니다.
+pub fn 다음_난이도_비트(설정: &난이도_설정, 이전_블록들: &[블록]) -> u32 {
+    let 마지막_블록 = match 이전_블록들.last() {
+        Some(블록) => 블록,
+        None => return 설정.초기_난이도_비트,
+    };
+    let 다음_id = 마지막_블록.id + 1;
+    if 설정.조정_주기 == 0 || 다음_id % 설정.조정_주기 != 0 {
+        return 마지막_블록.난이도_비트;
+    }
+
+    let 시작_위치 = 이전_블록들.len().saturating_sub(설정.조정_주기 as usize + 1);
+    let 시작_블록 = &이전_블록들[시작_위치];
+    let 구간_수 = (마지막_블록.id - 시작_블록.id) as i64;
+    let 기대_시간 = 구간_수 * 설정.목표_블록_시간;
+    if 기대_시간 <= 0 {
+        return 마지막_블록.난이도_비트;
+    }
+    let 실제_시간 = (마지막_블록.타임스탬프 - 시작_블록.타임스탬프).clamp(기대_시간 / 4, 기대_시간 * 4);
+
+    let 최대_목표 = 설정.최대_목표();
+    let 이전_목표 = 목표값::압축에서(마지막_블록.난이도_비트).unwrap_or(최대_목표);
+    let 새_목표 = 이전_목표.비율_조정(실제_시간.max(1) as u64, 기대_시간 as u64);
+    새_목표.min(최대_목표).압축으로()
+}
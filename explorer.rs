@@ -0,0 +1,97 @@
+use crate::rpc::{self, 대기_호출, 호출_오류};
+use hyper::{header, Body, Method, Request, Response, StatusCode};
+use serde_json::{json, Map, Value};
+use std::{convert::Infallible, net::SocketAddr};
+use tokio::sync::mpsc;
+
+// 블록 탐색기용 읽기 전용 REST API 입니다. 응답은 모두 JSON 이고, 조회는 JSON-RPC 와 같은 메서드로 합니다.
+//
+//   GET /blocks?page=1&limit=20        높은 블록부터 한 쪽 (limit 는 최대 100)
+//   GET /blocks/<높이 또는 해시>        블록 하나
+//   GET /search?q=<검색어>&page=&limit= 데이터에 검색어가 모두 들어 있는 블록들
+//   GET /stats?window=100              높이, 평균 블록 시간, 난이도 변화
+//
+// 쪽 응답은 {"total", "page", "limit", "blocks"} 이고, 오류는 {"error": "..."} 입니다.
+
+pub fn 서버_시작(주소: SocketAddr, 송신자: mpsc::UnboundedSender<대기_호출>) -> Result<(), hyper::Error> {
+    rpc::http_서버_시작(주소, "블록 탐색기 API", 송신자, http_처리)
+}
+
+fn json_응답(상태: StatusCode, 값: Value) -> Response<Body> {
+    let mut 응답 = Response::new(Body::from(값.to_string()));
+    *응답.status_mut() = 상태;
+    응답.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    응답
+}
+
+fn 오류_응답(상태: StatusCode, 메시지: &str) -> Response<Body> {
+    json_응답(상태, json!({ "error": 메시지 }))
+}
+
+// 경로를 메서드와 인자로 바꿉니다. 숫자 쿼리 값은 숫자로 넘겨 rpc 쪽 검증을 그대로 씁니다.
+fn 경로_해석(경로: &str, 쿼리: &Map<String, Value>) -> Option<(&'static str, Value)> {
+    let 조각들: Vec<&str> = 경로.trim_matches('/').split('/').collect();
+    let 인자 = |이름들: &[&str]| -> Value {
+        let 항목들: Map<String, Value> = 이름들
+            .iter()
+            .filter_map(|이름| 쿼리.get(*이름).map(|값| (이름.to_string(), 값.clone())))
+            .collect();
+        Value::Object(항목들)
+    };
+    match 조각들.as_slice() {
+        ["blocks"] => Some(("getBlocks", 인자(&["page", "limit"]))),
+        ["blocks", 블록] => Some(match 블록.parse::<u64>() {
+            Ok(높이) => ("getBlockByHeight", json!({ "height": 높이 })),
+            Err(_) => ("getBlockByHash", json!({ "hash": 블록 })),
+        }),
+        ["search"] => Some(("searchBlocks", 인자(&["query", "page", "limit"]))),
+        ["stats"] => Some(("getChainStats", 인자(&["window"]))),
+        _ => None,
+    }
+}
+
+// 쿼리 문자열을 이름별 값으로 바꿉니다. q 는 query 로 받고, 숫자로 읽히는 값은 숫자로 둡니다.
+fn 쿼리_해석(쿼리: &str) -> Map<String, Value> {
+    url::form_urlencoded::parse(쿼리.as_bytes())
+        .map(|(이름, 값)| {
+            let 이름 = if 이름 == "q" { "query".to_string() } else { 이름.into_owned() };
+            let 값 = match 값.parse::<u64>() {
+                Ok(숫자) if 이름 != "query" => Value::from(숫자),
+                _ => Value::from(값.into_owned()),
+            };
+            (이름, 값)
+        })
+        .collect()
+}
+
+fn 오류_상태(오류: &호출_오류) -> StatusCode {
+    match 오류.코드 {
+        -32602 => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn http_처리(
+    요청: Request<Body>,
+    송신자: mpsc::UnboundedSender<대기_호출>,
+) -> Result<Response<Body>, Infallible> {
+    if 요청.method() != Method::GET {
+        let mut 응답 = 오류_응답(StatusCode::METHOD_NOT_ALLOWED, "GET 만 받습니다");
+        응답.headers_mut()
+            .insert(header::ALLOW, header::HeaderValue::from_static("GET"));
+        return Ok(응답);
+    }
+    let 쿼리 = 쿼리_해석(요청.uri().query().unwrap_or(""));
+    let (메서드, 인자) = match 경로_해석(요청.uri().path(), &쿼리) {
+        Some(호출) => 호출,
+        None => return Ok(오류_응답(StatusCode::NOT_FOUND, "없는 경로입니다")),
+    };
+    Ok(match rpc::호출(&송신자, 메서드.to_string(), 인자).await {
+        Ok(Value::Null) => 오류_응답(StatusCode::NOT_FOUND, "찾는 블록이 없습니다"),
+        Ok(값) => json_응답(StatusCode::OK, 값),
+        Err(오류) => 오류_응답(오류_상태(&오류), &오류.메시지),
+    })
+}
@@ -0,0 +1,454 @@
+use super::블록;
+use crate::{
+    codec,
+    difficulty::난이도_설정,
+    state::{상태, 상태_오류},
+    storage::{BlockStore, 파일_저장소},
+    target::목표값,
+};
+use log::{info, warn};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+// 명세 파일 없이 띄운 노드가 쓰는 제네시스 시각입니다. 모든 노드가 같은 값을 써야 같은 블록이 나옵니다.
+const 기본_타임스탬프: i64 = 1_688_169_600;
+const 기본_데이터: &str = "제네시스!";
+
+struct 체인_정보 {
+    체인_id: String,
+    제네시스_해시: String,
+}
+
+static 체인: OnceCell<체인_정보> = OnceCell::new();
+
+// 초기 상태에서 주소에 넣어 두는 금액입니다.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct 할당 {
+    #[serde(rename = "address")]
+    pub 주소: String,
+    #[serde(rename = "amount")]
+    pub 금액: u64,
+}
+
+// 체인을 처음 시작할 때의 약속입니다. 이 값들이 하나라도 다르면 다른 체인이므로 체인 ID 도 달라집니다.
+//
+// 명세 파일 (--genesis 또는 CHAIN_GENESIS)
+//   network = "test"
+//   timestamp = 1688169600
+//   data = "제네시스!"
+//   difficulty_bits = 0x2000ffff
+//   max_target_bits = 0x207fffff
+//   retarget_interval = 10
+//   target_block_time_secs = 10
+//   [[alloc]]
+//   address = "<ed25519 공개키 hex>"
+//   amount = 1000
+//
+// difficulty_bits 는 제네시스 블록의 난이도이자 재조정의 출발점입니다. 재조정 규칙(max_target_bits,
+// retarget_interval, target_block_time_secs)도 블록 검증에 쓰는 합의 규칙이라 여기에 두고, 생략하면 기본값을 씁니다.
+// nonce, hash, chain_id 는 init-genesis 가 채워 넣습니다. 있으면 노드를 시작할 때 다시 계산한 값과 맞춰 봅니다.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct 제네시스_명세 {
+    // 사람이 알아보기 위한 이름입니다. main, test 처럼 씁니다.
+    #[serde(rename = "network")]
+    pub 네트워크: String,
+    #[serde(rename = "timestamp")]
+    pub 타임스탬프: i64,
+    #[serde(rename = "data", default)]
+    pub 데이터: String,
+    #[serde(rename = "difficulty_bits")]
+    pub 난이도_비트: u32,
+    #[serde(rename = "max_target_bits", default = "기본_최대_목표_비트")]
+    pub 최대_목표_비트: u32,
+    #[serde(rename = "retarget_interval", default = "기본_조정_주기")]
+    pub 조정_주기: u64,
+    #[serde(rename = "target_block_time_secs", default = "기본_목표_블록_시간")]
+    pub 목표_블록_시간: i64,
+    #[serde(rename = "alloc", default)]
+    pub 할당들: Vec<할당>,
+    #[serde(rename = "nonce", default, skip_serializing_if = "Option::is_none")]
+    pub 논스: Option<u64>,
+    #[serde(rename = "hash", default, skip_serializing_if = "Option::is_none")]
+    pub 해시: Option<String>,
+    #[serde(rename = "chain_id", default, skip_serializing_if = "Option::is_none")]
+    pub 체인_id: Option<String>,
+}
+
+fn 기본_최대_목표_비트() -> u32 {
+    난이도_설정::default().최대_목표_비트
+}
+
+fn 기본_조정_주기() -> u64 {
+    난이도_설정::default().조정_주기
+}
+
+fn 기본_목표_블록_시간() -> i64 {
+    난이도_설정::default().목표_블록_시간
+}
+
+#[derive(Debug)]
+pub enum 제네시스_오류 {
+    파일_읽기 { 경로: PathBuf, 오류: io::Error },
+    파일_쓰기 { 경로: PathBuf, 오류: io::Error },
+    파일_형식 { 경로: PathBuf, 오류: String },
+    잘못된_값 { 항목: &'static str, 사유: String },
+    // 명세에 기록된 값과 명세 내용으로 다시 계산한 값이 다릅니다.
+    기록과_다름 { 항목: &'static str, 기록: String, 계산: String },
+    저장소(io::Error),
+    저장된_제네시스_다름 { 저장된: String, 명세: String },
+}
+
+impl fmt::Display for 제네시스_오류 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            제네시스_오류::파일_읽기 { 경로, 오류 } => {
+                write!(f, "제네시스 명세 {} 를 읽을 수 없습니다: {}", 경로.display(), 오류)
+            }
+            제네시스_오류::파일_쓰기 { 경로, 오류 } => {
+                write!(f, "제네시스 명세 {} 를 쓸 수 없습니다: {}", 경로.display(), 오류)
+            }
+            제네시스_오류::파일_형식 { 경로, 오류 } => {
+                write!(f, "제네시스 명세 {} 의 형식이 잘못되었습니다: {}", 경로.display(), 오류)
+            }
+            제네시스_오류::잘못된_값 { 항목, 사유 } => write!(f, "제네시스 명세의 {} 가 잘못되었습니다: {}", 항목, 사유),
+            제네시스_오류::기록과_다름 { 항목, 기록, 계산 } => write!(
+                f,
+                "제네시스 명세의 {} 가 {} 로 기록되어 있지만 명세로 계산하면 {} 입니다. 명세를 바꿨다면 init-genesis 를 다시 실행하세요",
+                항목, 기록, 계산
+            ),
+            제네시스_오류::저장소(e) => write!(f, "저장소를 다룰 수 없습니다: {}", e),
+            제네시스_오류::저장된_제네시스_다름 { 저장된, 명세 } => write!(
+                f,
+                "저장소의 제네시스 {} 가 명세의 제네시스 {} 와 다릅니다. 다른 네트워크의 데이터 디렉토리입니다",
+                저장된, 명세
+            ),
+        }
+    }
+}
+
+impl 제네시스_명세 {
+    // 명세 파일을 주지 않은 노드끼리는 네트워크 이름과 난이도 규칙이 같으면 같은 제네시스를 만듭니다.
+    pub fn 기본(네트워크: &str, 난이도: &난이도_설정) -> Self {
+        제네시스_명세 {
+            네트워크: 네트워크.to_string(),
+            타임스탬프: 기본_타임스탬프,
+            데이터: 기본_데이터.to_string(),
+            난이도_비트: 난이도.초기_난이도_비트,
+            최대_목표_비트: 난이도.최대_목표_비트,
+            조정_주기: 난이도.조정_주기,
+            목표_블록_시간: 난이도.목표_블록_시간,
+            할당들: vec![],
+            논스: None,
+            해시: None,
+            체인_id: None,
+        }
+    }
+
+    pub fn 파일에서(경로: &Path) -> Result<Self, 제네시스_오류> {
+        let 내용 = fs::read_to_string(경로).map_err(|오류| 제네시스_오류::파일_읽기 {
+            경로: 경로.to_path_buf(),
+            오류,
+        })?;
+        let 명세: 제네시스_명세 = toml::from_str(&내용).map_err(|e| 제네시스_오류::파일_형식 {
+            경로: 경로.to_path_buf(),
+            오류: e.to_string(),
+        })?;
+        명세.검증()?;
+        Ok(명세)
+    }
+
+    pub fn 파일에_쓰기(&self, 경로: &Path) -> Result<(), 제네시스_오류> {
+        let 쓰기_오류 = |오류| 제네시스_오류::파일_쓰기 {
+            경로: 경로.to_path_buf(),
+            오류,
+        };
+        let 내용 = toml::to_string_pretty(self)
+            .map_err(|e| 쓰기_오류(io::Error::new(io::ErrorKind::InvalidData, e.to_string())))?;
+        fs::write(경로, 내용).map_err(쓰기_오류)
+    }
+
+    fn 검증(&self) -> Result<(), 제네시스_오류> {
+        if self.네트워크.is_empty()
+            || !self
+                .네트워크
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        {
+            return Err(제네시스_오류::잘못된_값 {
+                항목: "network",
+                사유: "영문 소문자, 숫자, - 와 _ 만 쓸 수 있습니다".to_string(),
+            });
+        }
+        let 비트_오류 = |항목, 비트: u32| 제네시스_오류::잘못된_값 {
+            항목,
+            사유: format!("{:#010x} 는 올바른 압축 목표값이 아닙니다", 비트),
+        };
+        let 초기 = 목표값::압축에서(self.난이도_비트).ok_or_else(|| 비트_오류("difficulty_bits", self.난이도_비트))?;
+        let 최대 = 목표값::압축에서(self.최대_목표_비트).ok_or_else(|| 비트_오류("max_target_bits", self.최대_목표_비트))?;
+        if 초기 > 최대 {
+            return Err(제네시스_오류::잘못된_값 {
+                항목: "difficulty_bits",
+                사유: "초기 목표값이 max_target_bits 보다 쉽습니다".to_string(),
+            });
+        }
+        if self.목표_블록_시간 <= 0 {
+            return Err(제네시스_오류::잘못된_값 {
+                항목: "target_block_time_secs",
+                사유: "0 보다 커야 합니다".to_string(),
+            });
+        }
+        if let Some(할당) = self
+            .할당들
+            .iter()
+            .find(|할당| hex::decode(&할당.주소).map_or(true, |바이트| 바이트.len() != 32))
+        {
+            return Err(제네시스_오류::잘못된_값 {
+                항목: "alloc.address",
+                사유: format!("{} 는 ed25519 공개키 hex 가 아닙니다", 할당.주소),
+            });
+        }
+        self.초기_상태().map_err(|e| 제네시스_오류::잘못된_값 {
+            항목: "alloc.amount",
+            사유: e.to_string(),
+        })?;
+        Ok(())
+    }
+
+    // 배치 = 네트워크 | 타임스탬프 i64 | 데이터 | 난이도_비트 u32 | 최대_목표_비트 u32 | 조정_주기 u64
+    //        | 목표_블록_시간 i64 | 할당 개수 u32 | (주소 | 금액 u64)...
+    // (문자열은 u32 길이 접두사) init-genesis 가 채우는 값들은 넣지 않습니다.
+    fn 인코딩(&self) -> Vec<u8> {
+        let mut 출력 = vec![];
+        codec::문자열_쓰기(&mut 출력, &self.네트워크);
+        출력.extend_from_slice(&self.타임스탬프.to_be_bytes());
+        codec::문자열_쓰기(&mut 출력, &self.데이터);
+        출력.extend_from_slice(&self.난이도_비트.to_be_bytes());
+        출력.extend_from_slice(&self.최대_목표_비트.to_be_bytes());
+        출력.extend_from_slice(&self.조정_주기.to_be_bytes());
+        출력.extend_from_slice(&self.목표_블록_시간.to_be_bytes());
+        출력.extend_from_slice(&(self.할당들.len() as u32).to_be_bytes());
+        for 할당 in &self.할당들 {
+            codec::문자열_쓰기(&mut 출력, &할당.주소);
+            출력.extend_from_slice(&할당.금액.to_be_bytes());
+        }
+        출력
+    }
+
+    fn 명세_해시(&self) -> Vec<u8> {
+        Sha256::digest(&self.인코딩()).to_vec()
+    }
+
+    // <네트워크>-<명세 해시 앞 8바이트 hex> 입니다. 예) main-3fa2c1d09b7e4a21
+    pub fn 계산한_체인_id(&self) -> String {
+        format!("{}-{}", self.네트워크, hex::encode(&self.명세_해시()[..8]))
+    }
+
+    // 블록을 검증하고 채굴할 때 쓰는 난이도 규칙입니다. 모든 노드가 명세에서 같은 값을 얻습니다.
+    pub fn 난이도_설정(&self) -> 난이도_설정 {
+        난이도_설정 {
+            초기_난이도_비트: self.난이도_비트,
+            최대_목표_비트: self.최대_목표_비트,
+            조정_주기: self.조정_주기,
+            목표_블록_시간: self.목표_블록_시간,
+        }
+    }
+
+    pub fn 초기_상태(&self) -> Result<상태, 상태_오류> {
+        상태::초기_할당(self.할당들.iter().map(|할당| (할당.주소.as_str(), 할당.금액)))
+    }
+
+    // 이전_해시 자리에 명세 해시를 넣어, 블록 해시가 할당까지 포함한 명세 전체를 덮게 합니다.
+    // 할당은 트랜잭션이 아니라 초기 상태로 들어가므로 블록 본문은 데이터뿐입니다.
+    fn 블록_만들기(&self, 논스: u64) -> 블록 {
+        let mut block = 블록 {
+            id: 0,
+            타임스탬프: self.타임스탬프,
+            이전_해시: hex::encode(self.명세_해시()),
+            데이터: self.데이터.clone(),
+            트랜잭션들: vec![],
+            머클_루트: 블록::머클_루트_계산(&self.데이터, &[]),
+            채굴자: String::new(),
+            논스,
+            해시: String::new(),
+            난이도_비트: self.난이도_비트,
+        };
+        block.해시 = hex::encode(block.헤더().해시());
+        block
+    }
+
+    // 논스를 0 부터 차례로 올리며 찾으므로 같은 명세면 어느 노드에서 채굴해도 같은 블록이 나옵니다.
+    pub fn 채굴(&self) -> Result<블록, 제네시스_오류> {
+        let 목표 = 목표값::압축에서(self.난이도_비트).ok_or_else(|| 제네시스_오류::잘못된_값 {
+            항목: "difficulty_bits",
+            사유: format!("{:#010x} 는 올바른 압축 목표값이 아닙니다", self.난이도_비트),
+        })?;
+        let mut block = self.블록_만들기(0);
+        while !목표.충족(&block.헤더().해시()) {
+            block.논스 += 1;
+        }
+        block.해시 = hex::encode(block.헤더().해시());
+        Ok(block)
+    }
+
+    // 노드를 시작할 때 씁니다. 기록된 논스가 있으면 그 논스로 블록을 만들어 작업 증명만 확인하고,
+    // 없으면 채굴합니다. 기록된 해시와 체인 ID 가 있으면 계산한 값과 같아야 합니다.
+    pub fn 제네시스_블록(&self) -> Result<블록, 제네시스_오류> {
+        let block = match self.논스 {
+            Some(논스) => {
+                let block = self.블록_만들기(논스);
+                let 충족 = 목표값::압축에서(self.난이도_비트).map_or(false, |목표| 목표.충족(&block.헤더().해시()));
+                if !충족 {
+                    return Err(제네시스_오류::잘못된_값 {
+                        항목: "nonce",
+                        사유: format!("논스 {} 로는 난이도 목표를 충족하지 않습니다. init-genesis 를 다시 실행하세요", 논스),
+                    });
+                }
+                block
+            }
+            None => self.채굴()?,
+        };
+        if let Some(기록) = self.해시.as_ref().filter(|기록| **기록 != block.해시) {
+            return Err(제네시스_오류::기록과_다름 {
+                항목: "hash",
+                기록: 기록.clone(),
+                계산: block.해시,
+            });
+        }
+        let 체인_id = self.계산한_체인_id();
+        if let Some(기록) = self.체인_id.as_ref().filter(|기록| **기록 != 체인_id) {
+            return Err(제네시스_오류::기록과_다름 {
+                항목: "chain_id",
+                기록: 기록.clone(),
+                계산: 체인_id,
+            });
+        }
+        Ok(block)
+    }
+}
+
+// init-genesis
+// 명세로 제네시스를 채굴해 논스, 해시, 체인 ID 를 명세 파일에 기록하고(파일이 없으면 표준 출력으로 보여 줍니다),
+// 데이터 디렉토리가 있으면 비어 있는 저장소에 제네시스를 씁니다. 이미 다른 제네시스로 시작한 저장소는 건드리지 않습니다.
+pub fn 초기화_실행(
+    명세: &제네시스_명세,
+    명세_경로: Option<&Path>,
+    데이터_디렉토리: Option<&Path>,
+) -> Result<(), 제네시스_오류> {
+    let block = 명세.채굴()?;
+    let 기록된_명세 = 제네시스_명세 {
+        논스: Some(block.논스),
+        해시: Some(block.해시.clone()),
+        체인_id: Some(명세.계산한_체인_id()),
+        ..명세.clone()
+    };
+    if 명세.논스.is_some() && 명세.논스 != 기록된_명세.논스 {
+        warn!("명세에 기록되어 있던 논스 {:?} 를 {} 로 바꿉니다", 명세.논스, block.논스);
+    }
+    match 명세_경로 {
+        Some(경로) => {
+            기록된_명세.파일에_쓰기(경로)?;
+            info!("제네시스 명세 {} 에 논스와 해시를 기록했습니다", 경로.display());
+        }
+        None => print!(
+            "{}",
+            toml::to_string_pretty(&기록된_명세).expect("명세는 TOML 로 쓸 수 있습니다")
+        ),
+    }
+
+    if let Some(디렉토리) = 데이터_디렉토리 {
+        let mut 저장소 = 파일_저장소::열기(디렉토리).map_err(제네시스_오류::저장소)?;
+        let 저장된_블록들 = 저장소.전체_블록_읽기().map_err(제네시스_오류::저장소)?;
+        match 저장된_블록들.first() {
+            None => {
+                저장소.블록_추가(&block).map_err(제네시스_오류::저장소)?;
+                info!("{} 에 제네시스 블록을 썼습니다", 디렉토리.display());
+            }
+            Some(저장된) if *저장된 == block => info!("{} 는 이미 이 제네시스로 시작했습니다", 디렉토리.display()),
+            Some(저장된) => {
+                return Err(제네시스_오류::저장된_제네시스_다름 {
+                    저장된: 저장된.해시.clone(),
+                    명세: block.해시,
+                })
+            }
+        }
+    }
+    println!("체인 ID: {}", 기록된_명세.계산한_체인_id());
+    println!("제네시스 해시: {}", block.해시);
+    Ok(())
+}
+
+// 토픽과 프로토콜 이름을 만들기 전에 한 번 정합니다.
+pub fn 체인_지정(체인_id: String, 제네시스_해시: String) {
+    if 체인.set(체인_정보 { 체인_id, 제네시스_해시 }).is_err() {
+        warn!("체인 ID 는 한 번만 정할 수 있습니다");
+    }
+}
+
+fn 지정된_체인() -> &'static 체인_정보 {
+    체인.get().expect("체인 ID 를 먼저 정해야 합니다")
+}
+
+pub fn 체인_id() -> &'static str {
+    &지정된_체인().체인_id
+}
+
+pub fn 제네시스_해시() -> &'static str {
+    &지정된_체인().제네시스_해시
+}
+
+// 토픽과 프로토콜 이름 앞에 붙여 다른 체인의 노드와 메시지가 섞이지 않게 합니다.
+pub fn 이름공간(이름: &str) -> String {
+    format!("/chatgptblockchain/{}/{}", 체인_id(), 이름)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn 난이도_규칙이_다르면_체인_id_가_다름() {
+        let 기본 = 제네시스_명세::기본("test", &난이도_설정::default());
+        let 달라진_명세들 = [
+            제네시스_명세 { 난이도_비트: 0x1f00_ffff, ..기본.clone() },
+            제네시스_명세 { 최대_목표_비트: 0x2000_ffff, ..기본.clone() },
+            제네시스_명세 { 조정_주기: 기본.조정_주기 + 1, ..기본.clone() },
+            제네시스_명세 { 목표_블록_시간: 기본.목표_블록_시간 * 2, ..기본.clone() },
+        ];
+        for 명세 in 달라진_명세들.iter() {
+            assert_ne!(명세.계산한_체인_id(), 기본.계산한_체인_id(), "{:?}", 명세);
+        }
+    }
+
+    #[test]
+    fn 생략한_재조정_규칙은_기본값() {
+        let 명세: 제네시스_명세 = toml::from_str(
+            "network = \"test\"\ntimestamp = 0\ndifficulty_bits = 0x2000ffff\nretarget_interval = 20\n",
+        )
+        .unwrap();
+        명세.검증().unwrap();
+        let 난이도 = 명세.난이도_설정();
+        assert_eq!(난이도.초기_난이도_비트, 0x2000_ffff);
+        assert_eq!(난이도.조정_주기, 20);
+        assert_eq!(난이도.최대_목표_비트, 난이도_설정::default().최대_목표_비트);
+        assert_eq!(난이도.목표_블록_시간, 난이도_설정::default().목표_블록_시간);
+    }
+
+    #[test]
+    fn 최대보다_쉬운_초기_난이도는_거부() {
+        let 명세 = 제네시스_명세 {
+            최대_목표_비트: 0x1f00_ffff,
+            ..제네시스_명세::기본("test", &난이도_설정::default())
+        };
+        assert!(matches!(
+            명세.검증(),
+            Err(제네시스_오류::잘못된_값 { 항목: "difficulty_bits", .. })
+        ));
+    }
+}
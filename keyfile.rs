@@ -0,0 +1,169 @@
+use crate::transaction;
+use libp2p::{identity, PeerId};
+use log::{info, warn};
+use once_cell::sync::OnceCell;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+const 기본_키_파일_이름: &str = "node.key";
+
+static 지정된_경로: OnceCell<PathBuf> = OnceCell::new();
+
+// 데이터 디렉토리가 있으면 그 안의 node.key, 없으면 현재 디렉토리의 node.key 입니다.
+pub fn 기본_경로(데이터_디렉토리: Option<&Path>) -> PathBuf {
+    match 데이터_디렉토리 {
+        Some(디렉토리) => 디렉토리.join(기본_키_파일_이름),
+        None => PathBuf::from(기본_키_파일_이름),
+    }
+}
+
+// 노드 설정에서 정한 경로를 KEYS 를 처음 쓰기 전에 지정합니다. 한 기기에서 여러 노드를 띄울 때는
+// 노드마다 다른 경로를 주어야 PEER_ID 가 겹치지 않습니다.
+pub fn 경로_지정(경로: PathBuf) {
+    if 지정된_경로.set(경로).is_err() {
+        warn!("노드 키 파일 경로는 한 번만 지정할 수 있습니다");
+    }
+}
+
+pub fn 키_파일_경로() -> PathBuf {
+    지정된_경로.get().cloned().unwrap_or_else(|| 기본_경로(None))
+}
+
+// 파일에는 ed25519 비밀키와 공개키 64바이트를 hex 한 줄로 씁니다. 노드 식별과 트랜잭션 서명에
+// 같은 키를 쓰므로 이 파일을 잃으면 그 주소의 잔액도 쓸 수 없습니다.
+pub fn 읽기(경로: &Path) -> io::Result<identity::Keypair> {
+    #[cfg(unix)]
+    {
+        let 권한 = fs::metadata(경로)?.permissions().mode();
+        if 권한 & 0o077 != 0 {
+            warn!("키 파일 {} 을 다른 사용자도 읽을 수 있습니다 (권한 {:o})", 경로.display(), 권한 & 0o777);
+        }
+    }
+    let 내용 = fs::read_to_string(경로)?;
+    let mut 바이트 = hex::decode(내용.trim())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("hex 가 아닙니다: {}", e)))?;
+    let 키 = identity::ed25519::Keypair::decode(&mut 바이트)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("ed25519 키가 아닙니다: {}", e)))?;
+    Ok(identity::Keypair::Ed25519(키))
+}
+
+// 임시 파일에 소유자만 읽을 수 있게 쓴 뒤 rename 하므로, 도중에 죽어도 이전 키 파일이 남습니다.
+fn 쓰기(경로: &Path, 키: &identity::ed25519::Keypair) -> io::Result<()> {
+    if let Some(디렉토리) = 경로.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(디렉토리)?;
+    }
+    let 임시_경로 = 경로.with_extension("key.tmp");
+    let _ = fs::remove_file(&임시_경로);
+    {
+        let mut 옵션 = OpenOptions::new();
+        옵션.write(true).create_new(true);
+        #[cfg(unix)]
+        옵션.mode(0o600);
+        let mut 파일 = 옵션.open(&임시_경로)?;
+        writeln!(파일, "{}", hex::encode(키.encode()))?;
+        파일.sync_all()?;
+    }
+    fs::rename(&임시_경로, 경로)
+}
+
+// 노드를 시작할 때 씁니다. 키 파일이 있으면 읽고, 없으면 새 키를 만들어 저장합니다.
+pub fn 불러오기_또는_생성(경로: &Path) -> io::Result<identity::Keypair> {
+    if 경로.exists() {
+        return 읽기(경로);
+    }
+    let 키 = identity::ed25519::Keypair::generate();
+    쓰기(경로, &키)?;
+    info!("새 노드 키를 만들어 {} 에 저장했습니다", 경로.display());
+    Ok(identity::Keypair::Ed25519(키))
+}
+
+// 이미 키 파일이 있으면 덮어쓰지 않습니다. 바꾸려면 교체를 씁니다.
+pub fn 생성(경로: &Path) -> io::Result<identity::Keypair> {
+    if 경로.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} 가 이미 있습니다. 바꾸려면 key rotate 를 쓰세요", 경로.display()),
+        ));
+    }
+    불러오기_또는_생성(경로)
+}
+
+// 새 키로 바꾸고, 이전 키는 같은 디렉토리에 <파일 이름>.old 로 남겨 그 주소의 잔액을 옮길 수 있게 합니다.
+// 이미 .old 가 있으면 .old.1, .old.2 처럼 비어 있는 이름을 골라 이전에 보관한 키를 덮어쓰지 않습니다.
+pub fn 교체(경로: &Path) -> io::Result<identity::Keypair> {
+    if 경로.exists() {
+        let 보관_경로 = 보관_경로(경로);
+        fs::rename(경로, &보관_경로)?;
+        info!("이전 키를 {} 로 옮겼습니다", 보관_경로.display());
+    }
+    불러오기_또는_생성(경로)
+}
+
+fn 보관_경로(경로: &Path) -> PathBuf {
+    let mut 기본 = 경로.as_os_str().to_owned();
+    기본.push(".old");
+    let mut 후보 = PathBuf::from(&기본);
+    let mut 번호 = 1u32;
+    while 후보.exists() {
+        let mut 이름 = 기본.clone();
+        이름.push(format!(".{}", 번호));
+        후보 = PathBuf::from(이름);
+        번호 += 1;
+    }
+    후보
+}
+
+fn 키_출력(경로: &Path, 키: &identity::Keypair) {
+    println!("키 파일: {}", 경로.display());
+    println!("Peer Id: {}", PeerId::from(키.public()));
+    if let identity::Keypair::Ed25519(키) = 키 {
+        println!("주소: {}", transaction::주소(&키.public()));
+    }
+}
+
+// key generate | key show | key rotate
+// 네트워크를 띄우지 않고 키 파일만 다룹니다. rotate 하면 PEER_ID 와 채굴 보상 주소가 모두 바뀝니다.
+pub fn 명령_실행(하위_명령: &str, 경로: &Path) -> io::Result<()> {
+    let 키 = match 하위_명령 {
+        "generate" => 생성(경로)?,
+        "show" => 읽기(경로)?,
+        "rotate" => 교체(경로)?,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "사용법: key <generate|show|rotate>",
+            ))
+        }
+    };
+    키_출력(경로, &키);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn 교체는_이전에_보관한_키를_덮어쓰지_않음() {
+        let 디렉토리 = std::env::temp_dir().join(format!("keyfile-교체-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&디렉토리);
+        let 경로 = 디렉토리.join(기본_키_파일_이름);
+
+        let 첫_키 = 불러오기_또는_생성(&경로).unwrap();
+        교체(&경로).unwrap();
+        교체(&경로).unwrap();
+
+        let 첫_보관 = 읽기(&디렉토리.join("node.key.old")).unwrap();
+        assert_eq!(첫_보관.public(), 첫_키.public());
+        assert!(디렉토리.join("node.key.old.1").exists());
+        assert!(!디렉토리.join("node.key.old.2").exists());
+
+        fs::remove_dir_all(&디렉토리).unwrap();
+    }
+}
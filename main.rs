@@ -1,5 +1,3 @@
-use serde_json::json;
-use chrono::prelude::*;
 use libp2p::{
     core::upgrade,
     futures::StreamExt,
@@ -12,142 +10,608 @@ use libp2p::{
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::time::Duration;
+use codec::이진_인코딩;
+use difficulty::난이도_설정;
+use mempool::{거래_거부_사유, 거래_풀};
+use merkle::{블록_항목, 머클_증명};
+use miner::채굴_템플릿;
+use orphan::고아_풀;
+use reorg::{재구성, 체인_이벤트};
+use std::{collections::HashSet, fmt, time::Duration};
+use state::{되돌리기_기록, 상태, 상태_오류};
+use storage::{BlockStore, 메모리_저장소, 파일_저장소};
+use target::목표값;
+use transaction::트랜잭션;
 use tokio::{
-    io::{stdin, AsyncBufReadExt, BufReader},
     select, spawn,
-    sync::mpsc,
-    time::sleep,
+    sync::{broadcast, mpsc},
+    time::{interval, sleep},
 };
 
-const 난이도: &str = "00";
-
+mod cli;
+mod codec;
+mod config;
+mod difficulty;
+mod explorer;
+mod genesis;
+mod keyfile;
+mod mempool;
+mod merkle;
+mod miner;
+mod orphan;
 mod peer2peer;
+mod protocol;
+mod reorg;
+mod rpc;
+mod state;
+mod storage;
+mod sync;
+mod target;
+mod transaction;
+mod ws;
 
 pub struct 앱 {
     pub 블록들: Vec<블록>,
+    저장소: Box<dyn BlockStore>,
+    난이도_설정: 난이도_설정,
+    // 이 노드가 채굴한 블록의 보상을 받을 주소
+    채굴자_주소: String,
+    // 제네시스 명세로 만든 블록과 명세의 할당을 담은 상태입니다. 주 체인은 항상 이 블록과 상태에서 시작합니다.
+    제네시스: 블록,
+    초기_상태: 상태,
+    상태: 상태,
+    // 블록들[i] 를 적용하기 전으로 되돌리는 기록이 되돌리기_기록들[i] 에 있습니다.
+    되돌리기_기록들: Vec<되돌리기_기록>,
+    pub 거래_풀: 거래_풀,
+    pub 고아_풀: 고아_풀,
+    // 체인_이벤트를 받을 구독자들. 받는 쪽이 닫히면 다음 알림 때 뺍니다.
+    구독자들: Vec<mpsc::UnboundedSender<체인_이벤트>>,
 }
 
+const 거래_풀_최대_개수: usize = 1_000;
+const 블록당_최대_트랜잭션: usize = 100;
+
 //
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct 블록 {
     id: u64,
     타임스탬프: i64,
     데이터: String,
+    트랜잭션들: Vec<트랜잭션>,
+    // 데이터와 트랜잭션들을 잎으로 하는 머클 트리의 루트. 블록 해시는 내용 대신 이 값을 덮습니다.
+    머클_루트: String,
+    채굴자: String,
     이전_해시: String,
     해시: String,
     논스: u64,
-    
+    난이도_비트: u32,
+}
+
+// 블록에서 해시가 덮는 부분입니다. 본문(데이터, 트랜잭션들)은 머클_루트로만 연결됩니다.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct 블록_헤더 {
+    id: u64,
+    타임스탬프: i64,
+    이전_해시: String,
+    머클_루트: String,
+    채굴자: String,
+    난이도_비트: u32,
+    논스: u64,
+}
+
+impl 블록_헤더 {
+    // 헤더의 이진 인코딩을 sha256 으로 해시합니다.
+    fn 해시(&self) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.인코딩());
+        hasher.finalize().to_vec()
+    }
 }
 
 impl 블록 {
-    pub fn new(id: u64, 이전_해시: String, 데이터: String) -> Self {
-        let now = Utc::now();
-        let (논스, 해시) = 블록_채굴(id, now.timestamp(), &이전_해시, &데이터);
-        Self {
-            id,
-            해시,
-            타임스탬프: now.timestamp(),
-            이전_해시,
+    fn 헤더(&self) -> 블록_헤더 {
+        블록_헤더 {
+            id: self.id,
+            타임스탬프: self.타임스탬프,
+            이전_해시: self.이전_해시.clone(),
+            머클_루트: self.머클_루트.clone(),
+            채굴자: self.채굴자.clone(),
+            난이도_비트: self.난이도_비트,
+            논스: self.논스,
+        }
+    }
+
+    fn 조립(헤더: 블록_헤더, 해시: String, 데이터: String, 트랜잭션들: Vec<트랜잭션>) -> Self {
+        블록 {
+            id: 헤더.id,
+            타임스탬프: 헤더.타임스탬프,
             데이터,
-            논스,
+            트랜잭션들,
+            머클_루트: 헤더.머클_루트,
+            채굴자: 헤더.채굴자,
+            이전_해시: 헤더.이전_해시,
+            해시,
+            논스: 헤더.논스,
+            난이도_비트: 헤더.난이도_비트,
         }
     }
-}
-//
-fn 해쉬_계산(id: u64, 타임스탬프: i64, 이전_해시: &str, 데이터: &str, 논스: u64) -> Vec<u8> {
-    let 데이터 = serde_json::to_value(&json!({
-        "id": id,
-        "이전_해시": 이전_해시,
-        "데이터": 데이터,
-        "타임스탬프": 타임스탬프,
-        "논스": 논스
-    })).unwrap();
-    let mut hasher = Sha256::new();
-    hasher.update(데이터.to_string().as_bytes());
-    hasher.finalize().to_vec()
-}
 
-//
-fn 블록_채굴(id: u64, 타임스탬프: i64, 이전_해시: &str, 데이터: &str) -> (u64, String) {
-    info!("블록 채굴...");
-    let mut 논스 = 0;
+    // 0번 잎은 데이터, i + 1 번 잎은 i 번째 트랜잭션입니다.
+    fn 잎_해시들(데이터: &str, 트랜잭션들: &[트랜잭션]) -> Vec<[u8; 32]> {
+        std::iter::once(블록_항목::데이터(데이터))
+            .chain(트랜잭션들.iter().map(블록_항목::트랜잭션))
+            .map(|항목| 항목.잎_해시())
+            .collect()
+    }
 
-    for _ in 0..std::usize::MAX {
-        if 논스 % 100000 == 0 {
-            info!("논스: {}", 논스);
+    fn 머클_루트_계산(데이터: &str, 트랜잭션들: &[트랜잭션]) -> String {
+        hex::encode(merkle::머클_루트(&블록::잎_해시들(데이터, 트랜잭션들)))
+    }
+
+    // 본문이 헤더의 머클 루트와 맞고 트랜잭션 서명이 모두 올바른지 봅니다. 이전 블록 없이
+    // 확인할 수 있으므로, 같은 헤더에 다른 본문을 담은 블록은 고아 풀에 넣기 전에 걸러집니다.
+    fn 본문_확인(&self) -> bool {
+        if 블록::머클_루트_계산(&self.데이터, &self.트랜잭션들) != self.머클_루트 {
+            warn!("id: {} 인 블록의 머클 루트가 내용과 맞지 않습니다", self.id);
+            return false;
         }
-        let 해시 = 해쉬_계산(id, 타임스탬프, 이전_해시, 데이터, 논스);
-        let 이진_해쉬 = 해쉬_이진수_표현(&해시);
-        if 이진_해쉬.starts_with(난이도) {
-            info!(
-                "성공! 논스: {}, 해시: {}, binary 해시: {}",
-                논스,
-                hex::encode(&해시),
-                이진_해쉬
+        if let Some(트랜잭션) = self.트랜잭션들.iter().find(|t| !t.서명_확인()) {
+            warn!(
+                "id: {} 인 블록에 서명이 올바르지 않은 트랜잭션 {} 이 있습니다",
+                self.id,
+                트랜잭션.해시()
             );
-            return (논스, hex::encode(해시));
+            return false;
         }
-        논스 += 1;
+        true
     }
-    (0, "".to_string())
-} 
+
+    pub fn 머클_증명(&self, 인덱스: usize) -> Option<(머클_증명, [u8; 32])> {
+        let 잎들 = 블록::잎_해시들(&self.데이터, &self.트랜잭션들);
+        let 증명 = merkle::증명_생성(&잎들, 인덱스)?;
+        Some((증명, 잎들[인덱스]))
+    }
+}
+
 //
+// 바이트마다 8자리로 채워야 앞쪽 0 의 개수가 실제 해시의 선행 0 비트 수와 같아집니다.
 fn 해쉬_이진수_표현(해시: &[u8]) -> String {
-    해시.iter().map(|z| format!("{:b}", z)).collect::<String>()
+    해시.iter().map(|z| format!("{:08b}", z)).collect::<String>()
+}
+
+// 블록 하나를 채굴하는 데 평균적으로 필요한 해시 시도 횟수입니다.
+fn 블록_작업량(block: &블록) -> u128 {
+    목표값::압축에서(block.난이도_비트).map_or(0, |목표| 목표.작업량())
 }
 
+fn 체인_작업량(chain: &[블록]) -> u128 {
+    chain
+        .iter()
+        .fold(0u128, |합계, block| 합계.saturating_add(블록_작업량(block)))
+}
+
+// 체인_선택_함수가 외부 체인을 받아들이지 않은 이유입니다.
+#[derive(Debug, PartialEq, Eq)]
+pub enum 체인_거부_사유 {
+    외부_체인_유효하지_않음,
+    작업량_부족 { 로칼: u128, 외부: u128 },
+    동일_작업량_해시_우선순위_낮음,
+}
+
+impl fmt::Display for 체인_거부_사유 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            체인_거부_사유::외부_체인_유효하지_않음 => write!(f, "외부 체인이 유효하지 않습니다"),
+            체인_거부_사유::작업량_부족 { 로칼, 외부 } => {
+                write!(f, "외부 체인의 누적 작업량({})이 로칼({})보다 크지 않습니다", 외부, 로칼)
+            }
+            체인_거부_사유::동일_작업량_해시_우선순위_낮음 => {
+                write!(f, "누적 작업량이 같고 외부 체인의 마지막 해시가 더 크거나 같습니다")
+            }
+        }
+    }
+}
+
+// 네트워크나 채굴기에서 받은 블록을 블록_수신_처리 가 어떻게 다루었는지 나타냅니다.
+#[derive(Debug, PartialEq, Eq)]
+pub enum 블록_수신_결과 {
+    // 주 체인의 팁이 바뀌었습니다. 곁가지로의 재구성도 포함합니다.
+    연결됨,
+    // 주 체인 중간이나 고아 풀의 블록에서 갈라진 곁가지에 붙어 풀에 넣었습니다.
+    곁가지,
+    // 부모를 몰라 풀에 넣었습니다. 이 해시의 블록을 받아 와야 이어 붙일 수 있습니다.
+    부모_없음(String),
+    이미_있음,
+    거부됨,
+}
 
 impl 앱 {
-    fn new() -> Self {
-        Self { 블록들: vec![] }
+    fn new(
+        저장소: Box<dyn BlockStore>,
+        난이도_설정: 난이도_설정,
+        채굴자_주소: String,
+        제네시스: 블록,
+        초기_상태: 상태,
+    ) -> Self {
+        Self {
+            블록들: vec![],
+            저장소,
+            난이도_설정,
+            채굴자_주소,
+            제네시스,
+            상태: 초기_상태.clone(),
+            초기_상태,
+            되돌리기_기록들: vec![],
+            거래_풀: 거래_풀::new(거래_풀_최대_개수),
+            고아_풀: 고아_풀::default(),
+            구독자들: vec![],
+        }
     }
 
-    fn 제네시스_함수(&mut self) {
-        let 제네시스블록_변수 = 블록 {
-            id: 0,
-            타임스탬프: Utc::now().timestamp(),//Utc::now() 함수는 Coordinated Universal Time (UTC) 기준으로 현재 시각을 반환
-            이전_해시: String::from("제네시스"),
-            데이터: String::from("제네시스!"),
-            논스: 2836,
-            해시: "1010f816a87f806bb0073dcf026a64fb40c946b5abee2573702828694d5b4c43".to_string(),
+    // 이후 주 체인에 블록이 붙거나 떨어질 때마다 체인_이벤트를 받을 채널을 엽니다.
+    pub fn 구독(&mut self) -> mpsc::UnboundedReceiver<체인_이벤트> {
+        let (송신자, 수신자) = mpsc::unbounded_channel();
+        self.구독자들.push(송신자);
+        수신자
+    }
+
+    fn 알림(&mut self, 이벤트들: Vec<체인_이벤트>) {
+        for 이벤트 in 이벤트들 {
+            self.구독자들.retain(|구독자| 구독자.send(이벤트.clone()).is_ok());
+        }
+    }
+
+    // 현재 체인 끝에 붙일 다음 블록이 가져야 하는 난이도_비트입니다.
+    fn 다음_난이도_비트(&self) -> u32 {
+        difficulty::다음_난이도_비트(&self.난이도_설정, &self.블록들)
+    }
+
+    // 현재 체인 끝 위에 데이터와 거래 풀에서 고른 트랜잭션들을 담은 블록을 채굴하기 위한 템플릿입니다.
+    fn 채굴_템플릿(&self, 데이터: String) -> 채굴_템플릿 {
+        let 마지막_블록 = self.블록들.last().expect("적어도 하나의 블록이 있어야 합니다");
+        let 트랜잭션들 = self.거래_풀.블록_템플릿_선택(&self.상태, 블록당_최대_트랜잭션);
+        채굴_템플릿 {
+            id: 마지막_블록.id + 1,
+            이전_해시: 마지막_블록.해시.clone(),
+            머클_루트: 블록::머클_루트_계산(&데이터, &트랜잭션들),
+            데이터,
+            트랜잭션들,
+            채굴자: self.채굴자_주소.clone(),
+            난이도_비트: self.다음_난이도_비트(),
+        }
+    }
+
+    // 주소가 다음에 보낼 트랜잭션이 써야 하는 논스입니다.
+    fn 다음_논스(&self, 주소: &str) -> u64 {
+        self.상태.계정(주소).논스
+    }
+
+    fn 잔액(&self, 주소: &str) -> u64 {
+        self.상태.계정(주소).잔액
+    }
+
+    fn 트랜잭션_추가(&mut self, 트랜잭션: 트랜잭션) -> Result<(), 거래_거부_사유> {
+        self.거래_풀.추가(트랜잭션, &self.상태)
+    }
+
+    // 초기 상태에서 체인의 블록들을 처음부터 차례로 적용합니다.
+    fn 상태_재구성(&self, chain: &[블록]) -> Result<(상태, Vec<되돌리기_기록>), 상태_오류> {
+        let mut 새_상태 = self.초기_상태.clone();
+        let mut 기록들 = Vec::with_capacity(chain.len());
+        for block in chain {
+            기록들.push(새_상태.블록_적용(block)?);
+        }
+        Ok((새_상태, 기록들))
+    }
+
+    // 저장소에 남아 있던 체인을 읽어 다시 검증한 뒤 메모리로 올립니다.
+    // 검증에 실패하면 비어 있는 상태로 시작하고, 저장소도 그 상태로 다시 씁니다.
+    // 다만 제네시스가 명세와 다르면 다른 네트워크의 데이터이므로 지우지 않고 오류를 돌려줍니다.
+    fn 저장소_불러오기(&mut self) -> Result<(), String> {
+        let 저장된_블록들 = match self.저장소.전체_블록_읽기() {
+            Ok(블록들) => 블록들,
+            Err(e) => {
+                error!("저장소를 읽을 수 없습니다: {}", e);
+                vec![]
+            }
         };
+        if let Some(저장된) = 저장된_블록들.first().filter(|저장된| **저장된 != self.제네시스) {
+            return Err(format!(
+                "저장소의 제네시스 {} 가 명세의 제네시스 {} 와 다릅니다",
+                저장된.해시, self.제네시스.해시
+            ));
+        }
+        let 재구성 = if self.체인_유효성_확인_함수(&저장된_블록들) {
+            self.상태_재구성(&저장된_블록들).map_err(|e| e.to_string())
+        } else {
+            Err("블록 검증 실패".to_string())
+        };
+        match 재구성 {
+            Ok((상태, 기록들)) => {
+                info!("저장소에서 블록 {}개를 불러왔습니다", 저장된_블록들.len());
+                self.블록들 = 저장된_블록들;
+                self.상태 = 상태;
+                self.되돌리기_기록들 = 기록들;
+            }
+            Err(사유) => {
+                warn!("저장된 체인이 유효하지 않아 버리고 새로 시작합니다: {}", 사유);
+                self.블록들 = vec![];
+                self.상태 = self.초기_상태.clone();
+                self.되돌리기_기록들 = vec![];
+            }
+        }
+        if let Err(e) = self.저장소.체인_교체(&self.블록들) {
+            error!("저장소를 정리할 수 없습니다: {}", e);
+        }
+        Ok(())
+    }
+
+    fn 제네시스_함수(&mut self) {
+        if !self.블록들.is_empty() {
+            return;
+        }
+        // 제네시스는 명세로 정해지므로 같은 체인의 모든 노드가 같은 블록을 가집니다.
+        let 제네시스블록_변수 = self.제네시스.clone();
+        if let Err(e) = self.저장소.블록_추가(&제네시스블록_변수) {
+            error!("제네시스 블록을 저장할 수 없습니다: {}", e);
+        }
+        let 기록 = self
+            .상태
+            .블록_적용(&제네시스블록_변수)
+            .expect("제네시스 블록은 상태에 적용할 수 있음");
+        self.되돌리기_기록들.push(기록);
+        self.알림(vec![체인_이벤트::연결됨(제네시스블록_변수.clone())]);
         self.블록들.push(제네시스블록_변수);
     }
+    // 블록이 체인에 붙었으면 true 를 돌려줍니다.
+    fn 블록_추가시도_함수(&mut self, block: 블록) -> bool {
+        match self.블록_유효성확인_함수(&block, &self.블록들) {
+        true => {
+            let 기록 = match self.상태.블록_적용(&block) {
+                Ok(기록) => 기록,
+                Err(e) => {
+                    error!("블록 추가 불가 - 상태에 적용할 수 없음: {}", e);
+                    return false;
+                }
+            };
+            if let Err(e) = self.저장소.블록_추가(&block) {
+                error!("블록을 저장할 수 없습니다: {}", e);
+            }
+            self.거래_풀.블록_반영(&block, &self.상태);
+            self.되돌리기_기록들.push(기록);
+            self.알림(vec![체인_이벤트::연결됨(block.clone())]);
+            self.블록들.push(block);
+            true
+        }
+        false => {
+            error!("블록 추가 불가 - 유효하지 않음");
+            false
+        }
+        }
+    }
+    // 팁 위에 바로 붙지 않는 블록은 버리지 않고 고아 풀에 모아 둡니다. 부모를 모르면 어떤 해시를
+    // 받아 와야 하는지 돌려주고, 곁가지가 주 체인보다 무거워지면 체인_선택_함수로 재구성합니다.
+    fn 블록_수신_처리(&mut self, block: 블록) -> 블록_수신_결과 {
+        if self.고아_풀.포함(&block.해시) || self.블록들.iter().any(|b| b.해시 == block.해시) {
+            return 블록_수신_결과::이미_있음;
+        }
+        let 팁_해시 = match self.블록들.last() {
+            Some(팁) => 팁.해시.clone(),
+            None => return 블록_수신_결과::거부됨,
+        };
+        if block.이전_해시 == 팁_해시 {
+            if !self.블록_추가시도_함수(block) {
+                return 블록_수신_결과::거부됨;
+            }
+            self.고아_처리();
+            return 블록_수신_결과::연결됨;
+        }
+
+        // 풀에 넣는 블록은 헤더 해시와 작업 증명, 그리고 부모 없이 볼 수 있는 본문이 맞아야 합니다.
+        // 본문을 보지 않으면 헤더만 베끼고 본문을 바꾼 블록이 자리를 차지해, 진짜 블록이 왔을 때
+        // 이미_있음 으로 버려집니다. 나머지 검증은 주 체인에 붙일 때 체인_유효성_확인_함수가 합니다.
+        let 해시 = block.헤더().해시();
+        let 작업_증명_맞음 = hex::encode(&해시) == block.해시
+            && 목표값::압축에서(block.난이도_비트).map_or(false, |목표| 목표.충족(&해시));
+        if !작업_증명_맞음 {
+            warn!("id: {} 인 블록의 해시나 작업 증명이 맞지 않아 고아 풀에 넣지 않습니다", block.id);
+            return 블록_수신_결과::거부됨;
+        }
+        if !block.본문_확인() {
+            return 블록_수신_결과::거부됨;
+        }
+
+        let 부모_앎 = self.고아_풀.포함(&block.이전_해시)
+            || self.블록들.iter().any(|b| b.해시 == block.이전_해시);
+        let 부모_해시 = block.이전_해시.clone();
+        self.고아_풀.추가(block);
+        if !부모_앎 {
+            info!("부모 {} 를 모르는 블록을 고아 풀에 넣습니다 (풀 {}개)", 부모_해시, self.고아_풀.개수());
+            return 블록_수신_결과::부모_없음(부모_해시);
+        }
+        if self.곁가지_평가() {
+            블록_수신_결과::연결됨
+        } else {
+            블록_수신_결과::곁가지
+        }
+    }
+
+    // 주 체인의 팁이 바뀐 뒤 호출합니다. 풀에서 새 팁의 자식들을 이어 붙이고 곁가지를 다시 비교합니다.
+    // 팁이 더 바뀌었으면 true 를 돌려줍니다.
+    fn 고아_처리(&mut self) -> bool {
+        let mut 바뀜 = false;
+        loop {
+            let 팁_해시 = match self.블록들.last() {
+                Some(팁) => 팁.해시.clone(),
+                None => return 바뀜,
+            };
+            let mut 붙음 = false;
+            for 해시 in self.고아_풀.자식들(&팁_해시) {
+                let block = self.고아_풀.제거(&해시).expect("풀에서 찾은 해시");
+                if self.블록_추가시도_함수(block) {
+                    붙음 = true;
+                    break;
+                }
+            }
+            if !붙음 {
+                break;
+            }
+            바뀜 = true;
+        }
+        self.곁가지_평가() || 바뀜
+    }
+
+    // 풀의 곁가지마다 주 체인과 만나는 분기점을 찾아 후보 체인을 만들고, 누적 작업량이 가장 큰
+    // 후보가 주 체인보다 무거우면 그 체인으로 교체합니다. 분기점이 없는 곁가지는 아직 조상을
+    // 받는 중이므로 건너뜁니다.
+    fn 곁가지_평가(&mut self) -> bool {
+        let 로칼_작업량 = 체인_작업량(&self.블록들);
+        let mut 최선: Option<(u128, usize, Vec<블록>)> = None;
+        for 끝_해시 in self.고아_풀.끝_해시들() {
+            let 경로 = self.고아_풀.조상_경로(&끝_해시);
+            let 분기점 = match 경로
+                .last()
+                .and_then(|뿌리| self.블록들.iter().position(|b| b.해시 == 뿌리.이전_해시))
+            {
+                Some(분기점) => 분기점,
+                None => continue,
+            };
+            let mut 후보 = self.블록들[..=분기점].to_vec();
+            후보.extend(경로.into_iter().rev().cloned());
+            let 작업량 = 체인_작업량(&후보);
+            if 작업량 > 로칼_작업량 && 최선.as_ref().map_or(true, |(최선_작업량, _, _)| 작업량 > *최선_작업량) {
+                최선 = Some((작업량, 분기점 + 1, 후보));
+            }
+        }
+        let (분기점_길이, 후보) = match 최선 {
+            Some((_, 분기점_길이, 후보)) => (분기점_길이, 후보),
+            None => return false,
+        };
+
+        let 이전_체인 = self.블록들.clone();
+        let 후보_해시들: HashSet<String> = 후보.iter().map(|b| b.해시.clone()).collect();
+        let 교체됨 = match self.체인_선택_함수(&이전_체인, 후보.clone()) {
+            Ok(선택된_체인) => match self.체인_교체_함수(선택된_체인) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("곁가지를 상태에 적용할 수 없음: {}", e);
+                    false
+                }
+            },
+            Err(사유) => {
+                info!("곁가지로 재구성하지 않음: {}", 사유);
+                false
+            }
+        };
+        if !교체됨 {
+            // 받아들일 수 없는 곁가지를 다음에 또 평가하지 않도록 처음 잘못된 블록과 그 자손들만
+            // 풀에서 뺍니다. 그 앞의 조상들은 다른 올바른 곁가지가 함께 쓰고 있을 수 있습니다.
+            if let Some(위치) = self.첫_잘못된_블록(&후보, 분기점_길이) {
+                let 뺀_개수 = self.고아_풀.가지_제거(&후보[위치]);
+                info!("곁가지의 블록 {} 부터 {}개를 고아 풀에서 뺍니다", 후보[위치].해시, 뺀_개수);
+            }
+            return false;
+        }
+
+        // 새 주 체인에 들어간 블록은 풀에서 빼고, 밀려난 블록은 나중에 다시 무거워질 수 있으니 곁가지로 남깁니다.
+        for 해시 in &후보_해시들 {
+            self.고아_풀.제거(해시);
+        }
+        for block in 이전_체인 {
+            if !후보_해시들.contains(&block.해시) {
+                self.고아_풀.추가(block);
+            }
+        }
+        self.고아_처리();
+        true
+    }
+
+    // 후보 체인에서 분기점_길이 번째부터 차례로 검증하고 상태에 적용해 보아, 처음으로 실패하는
+    // 블록의 위치를 돌려줍니다. 앞의 블록들은 주 체인과 같아야 합니다.
+    fn 첫_잘못된_블록(&self, 후보: &[블록], 분기점_길이: usize) -> Option<usize> {
+        let mut 새_상태 = self.상태.clone();
+        let mut 새_기록들 = self.되돌리기_기록들.clone();
+        while 새_기록들.len() > 분기점_길이 {
+            새_상태.되돌리기(새_기록들.pop().expect("길이를 확인했음"));
+        }
+        (분기점_길이..후보.len()).find(|&위치| {
+            !self.블록_유효성확인_함수(&후보[위치], &후보[..위치]) || 새_상태.블록_적용(&후보[위치]).is_err()
+        })
+    }
     //
-    fn 블록_추가시도_함수(&mut self, block: 블록) {
-        let 마지막_블록 = self.블록들.last().expect("적어도 하나의 블록이 존재");
-        match self.블록_유효성확인_함수(&block, 마지막_블록) {
-        true => self.블록들.push(block),
-        false => error!("블록 추가 불가 - 유효하지 않음"),
+    // 공통 조상까지 상태를 되돌린 뒤 새 체인의 나머지 블록들을 적용합니다.
+    // 도중에 하나라도 실패하면 아무것도 바꾸지 않고 오류를 돌려줍니다.
+    // 성공하면 떼어 낸 블록의 트랜잭션을 거래 풀로 돌려보내고, 해제·연결 순서대로 구독자에게 알립니다.
+    fn 체인_교체_함수(&mut self, 체인: Vec<블록>) -> Result<(), 상태_오류> {
+        let 공통_길이 = reorg::공통_길이(&self.블록들, &체인);
+
+        let mut 새_상태 = self.상태.clone();
+        let mut 새_기록들 = self.되돌리기_기록들.clone();
+        while 새_기록들.len() > 공통_길이 {
+            새_상태.되돌리기(새_기록들.pop().expect("길이를 확인했음"));
+        }
+        for block in &체인[공통_길이..] {
+            새_기록들.push(새_상태.블록_적용(block)?);
+        }
+
+        info!(
+            "블록 {}개를 되돌리고 {}개를 적용해 체인을 교체합니다",
+            self.블록들.len() - 공통_길이,
+            체인.len() - 공통_길이
+        );
+        if let Err(e) = self.저장소.체인_교체(&체인) {
+            error!("체인을 저장할 수 없습니다: {}", e);
+        }
+        let 재구성 = 재구성 {
+            해제된: std::mem::replace(&mut self.블록들, 체인).split_off(공통_길이),
+            연결된: self.블록들[공통_길이..].to_vec(),
+        };
+        self.상태 = 새_상태;
+        self.되돌리기_기록들 = 새_기록들;
+        self.거래_풀.정리(&self.상태);
+        let 되돌린_개수 = self.거래_풀.되돌리기(재구성.되돌릴_트랜잭션들(), &self.상태);
+        if 되돌린_개수 > 0 {
+            info!("떼어 낸 블록의 트랜잭션 {}개를 거래 풀로 돌려보냈습니다", 되돌린_개수);
         }
+        self.알림(재구성.이벤트들());
+        Ok(())
     }
     //
-    fn 블록_유효성확인_함수(&self, block: &블록, previous_block: &블록) -> bool {
+    // 이전_블록들은 검증할 블록 바로 앞까지의 체인입니다. 난이도 스케줄을 다시 계산하는 데 씁니다.
+    fn 블록_유효성확인_함수(&self, block: &블록, 이전_블록들: &[블록]) -> bool {
+        let previous_block = match 이전_블록들.last() {
+            Some(previous_block) => previous_block,
+            None => {
+                warn!("id: {} 인 블록 앞에 이전 블록이 없습니다", block.id);
+                return false;
+            }
+        };
         if block.이전_해시 != previous_block.해시 {
             warn!("id: {} 인 블록은 잘못된 이전 해시를 가짐", block.id);
             return false;
-        } else if !해쉬_이진수_표현(
-            &hex::decode(&block.해시).expect("16진수로부터 디코딩할 수 있음."),
-        )
-        .starts_with(난이도)
-        {
+        } else if block.난이도_비트 != difficulty::다음_난이도_비트(&self.난이도_설정, 이전_블록들) {
+            warn!(
+                "id: {} 인 블록의 난이도 비트 {:#010x} 가 스케줄과 다릅니다",
+                block.id, block.난이도_비트
+            );
+            return false;
+        } else if block.타임스탬프 < previous_block.타임스탬프 {
+            warn!("id: {} 인 블록의 타임스탬프가 이전 블록보다 앞섭니다", block.id);
+            return false;
+        } else if !목표값::압축에서(block.난이도_비트).map_or(false, |목표| {
+            hex::decode(&block.해시).map_or(false, |해시| 목표.충족(&해시))
+        }) {
             warn!("id: {} 인 블록의 난이도가 잘못되었습니다.", block.id);
             return false;
+        } else if !block.본문_확인() {
+            return false;
         } else if block.id != previous_block.id + 1 {
             warn!(
                 "id: {} 를 가진 블록은 유효하지 않은 난이도를 가지고 있습니다.: {}",
                 block.id, previous_block.id
             );
             return false;
-        } else if hex::encode(해쉬_계산(
-            block.id,
-            block.타임스탬프,
-            &block.이전_해시,
-            &block.데이터,
-            block.논스,
-        )) != block.해시
-        {
+        } else if hex::encode(block.헤더().해시()) != block.해시 {
             warn!("블록 id: {} 의 해시가 올바르지 않습니다", block.id);
             return false;
         }
@@ -158,12 +622,15 @@ impl 앱 {
         let mut i = 0;
         while i < chain.len() {
             if i == 0 {
-            i += 1;
-            continue;
+                if chain[0] != self.제네시스 {
+                    warn!("제네시스 {} 가 우리 체인의 제네시스와 다릅니다", chain[0].해시);
+                    return false;
+                }
+                i += 1;
+                continue;
             }
-            let 첫번째 = chain.get(i - 1).expect("존재해야 합니다");
             let 두번째 = chain.get(i).expect("존재해야 합니다");
-            if !self.블록_유효성확인_함수(두번째, 첫번째) {
+            if !self.블록_유효성확인_함수(두번째, &chain[..i]) {
                 return false;
             }
             i += 1;
@@ -171,26 +638,36 @@ impl 앱 {
         true
     }
 
-    // 
-    fn 체인_선택_함수(&mut self, 로칼: Vec<블록>, 외부: Vec<블록>) -> Vec<블록> {
-        let 로칼_유효 = self.체인_유효성_확인_함수(&로칼);
-        let 외부remote_유효 = self.체인_유효성_확인_함수(&외부);
-    
-        match (로칼_유효, 외부remote_유효) {
-            (true, true) => {
-                if 로칼.len() >= 외부.len() {
-                    로칼
-                } else {
-                    외부
-                }
-            }
-            (false, true) => {
-                외부
-            }
-            (true, false) => {
-                로칼
-            }
-            _ => panic!("로칼과 외부 체인 모두 유효하지 않습니다"),
+    // 블록 개수가 아니라 누적 작업량이 더 큰 체인을 고릅니다. 쉬운 블록을 많이 붙인 체인이
+    // 정직한 체인을 덮어쓰지 못하게 하기 위함입니다. 작업량이 같으면 마지막 블록의 해시가
+    // 더 작은 쪽을 골라 모든 노드가 같은 결론을 내리게 합니다.
+    // Ok 이면 외부 체인으로 교체하고, Err 이면 로칼 체인을 그대로 유지합니다.
+    fn 체인_선택_함수(&self, 로칼: &[블록], 외부: Vec<블록>) -> Result<Vec<블록>, 체인_거부_사유> {
+        if !self.체인_유효성_확인_함수(&외부) {
+            return Err(체인_거부_사유::외부_체인_유효하지_않음);
+        }
+        if !self.체인_유효성_확인_함수(로칼) {
+            return Ok(외부);
+        }
+
+        let 로칼_작업량 = 체인_작업량(로칼);
+        let 외부_작업량 = 체인_작업량(&외부);
+        if 외부_작업량 > 로칼_작업량 {
+            return Ok(외부);
+        }
+        if 외부_작업량 < 로칼_작업량 {
+            return Err(체인_거부_사유::작업량_부족 {
+                로칼: 로칼_작업량,
+                외부: 외부_작업량,
+            });
+        }
+
+        let 로칼_마지막_해시 = 로칼.last().map(|b| b.해시.as_str());
+        let 외부_마지막_해시 = 외부.last().map(|b| b.해시.as_str());
+        if 외부_마지막_해시 < 로칼_마지막_해시 {
+            Ok(외부)
+        } else {
+            Err(체인_거부_사유::동일_작업량_해시_우선순위_낮음)
         }
     }
     
@@ -198,12 +675,87 @@ impl 앱 {
 
 #[tokio::main]
 async fn main() {
-    // pretty_env_logger 는 Rust 프로그래밍 언어에서의 라이브러리 이름입니다. 이 라이브러리는 Rust 프로젝트에서 환경 변수를 통해 로깅을 설정할 수 있도록 도와주는 로깅 라이브러리입니다.
-    pretty_env_logger::init();
+    // key <generate|show|rotate> [인자...] 로 실행하면 노드를 띄우지 않고 키 파일만 다루고,
+    // init-genesis [인자...] 로 실행하면 제네시스 명세로 제네시스를 만들어 기록만 합니다.
+    let 인자들: Vec<String> = std::env::args().skip(1).collect();
+    let (키_명령, 제네시스_초기화, 설정_인자들) = match 인자들.split_first() {
+        Some((명령, 나머지)) if 명령 == "key" => match 나머지.split_first() {
+            Some((하위_명령, 나머지)) if !하위_명령.starts_with("--") => (Some(하위_명령.as_str()), false, 나머지),
+            _ => (Some("show"), false, 나머지),
+        },
+        Some((명령, 나머지)) if 명령 == "init-genesis" => (None, true, 나머지),
+        _ => (None, false, &인자들[..]),
+    };
+    // 로거를 만들기 전이므로 설정 오류는 표준 에러로 바로 알립니다.
+    let 설정 = match config::노드_설정::불러오기(설정_인자들) {
+        Ok(설정) => 설정,
+        Err(e) => {
+            eprintln!("설정 오류: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    // pretty_env_logger 는 Rust 프로그래밍 언어에서의 라이브러리 이름입니다. 이 라이브러리는 Rust 프로젝트에서 로깅을 설정할 수 있도록 도와주는 로깅 라이브러리입니다.
+    // 로그 필터는 RUST_LOG 와 같은 형식이며 설정의 log 항목으로 정합니다.
+    pretty_env_logger::formatted_builder()
+        .parse_filters(&설정.로그)
+        .init();
+    keyfile::경로_지정(설정.키_파일.clone());
+
+    if let Some(하위_명령) = 키_명령 {
+        if let Err(e) = keyfile::명령_실행(하위_명령, &keyfile::키_파일_경로()) {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let 제네시스_명세 = match &설정.제네시스_파일 {
+        Some(경로) => match genesis::제네시스_명세::파일에서(경로) {
+            Ok(명세) => 명세,
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(2);
+            }
+        },
+        None => genesis::제네시스_명세::기본(&설정.네트워크, &설정.난이도),
+    };
+    if 제네시스_초기화 {
+        if let Err(e) = genesis::초기화_실행(
+            &제네시스_명세,
+            설정.제네시스_파일.as_deref(),
+            설정.데이터_디렉토리.as_deref(),
+        ) {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // 저장된 체인이 있으면 저장소_불러오기 가 이 블록과 같은지 확인합니다.
+    let 제네시스 = match 제네시스_명세.제네시스_블록() {
+        Ok(block) => block,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let 초기_상태 = 제네시스_명세
+        .초기_상태()
+        .expect("명세를 읽을 때 할당을 확인했습니다");
+    genesis::체인_지정(제네시스_명세.계산한_체인_id(), 제네시스.해시.clone());
 
     info!("Peer Id: {}", peer2peer::PEER_ID.clone());
-    let (반응_송신자, mut 반응_수신) = mpsc::unbounded_channel();
+    info!(
+        "네트워크: {} (체인 ID {}, 제네시스 {})",
+        제네시스_명세.네트워크,
+        genesis::체인_id(),
+        genesis::제네시스_해시()
+    );
     let (초기_송신자, mut 초기_수신) = mpsc::unbounded_channel();
+    let (채굴_송신자, mut 채굴_수신) = mpsc::unbounded_channel();
+    // 서버를 띄우지 않아도 송신자를 살려 두어 아래 select 의 수신 쪽이 닫히지 않게 합니다.
+    let (rpc_송신자, mut rpc_수신) = mpsc::unbounded_channel();
+    let (이벤트_방송, _) = broadcast::channel(ws::방송_버퍼_크기);
 
     let auth_keys = Keypair::<X25519Spec>::new()
         .into_authentic(&peer2peer::KEYS)
@@ -215,7 +767,36 @@ async fn main() {
         .multiplex(mplex::MplexConfig::new())
         .boxed();
 
-    let 처리_하자 = peer2peer::앱동작_구조체::new(앱::new(), 반응_송신자, 초기_송신자.clone()).await;
+    // 데이터 디렉토리가 주어지면 그 디렉토리에 체인을 기록하고, 없으면 예전처럼 메모리에만 둡니다.
+    let 저장소: Box<dyn BlockStore> = match &설정.데이터_디렉토리 {
+        Some(디렉토리) => match 파일_저장소::열기(디렉토리) {
+            Ok(저장소) => Box::new(저장소),
+            Err(e) => {
+                error!("데이터 디렉토리 {} 를 열 수 없습니다: {}", 디렉토리.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => {
+            info!("데이터 디렉토리가 없어 메모리 저장소를 사용합니다");
+            Box::new(메모리_저장소::default())
+        }
+    };
+    // 난이도 규칙은 합의 규칙이므로 노드 설정이 아니라 제네시스 명세에서 가져옵니다.
+    let mut 앱_상태 = 앱::new(
+        저장소,
+        제네시스_명세.난이도_설정(),
+        peer2peer::노드_주소(),
+        제네시스,
+        초기_상태,
+    );
+    if let Err(사유) = 앱_상태.저장소_불러오기() {
+        error!("{}. 다른 네트워크의 데이터 디렉토리가 아닌지 확인하세요", 사유);
+        std::process::exit(1);
+    }
+    let mut 체인_이벤트_수신 = 앱_상태.구독();
+
+    let 처리_하자 =
+        peer2peer::앱동작_구조체::new(앱_상태, 초기_송신자.clone(), 채굴_송신자, 이벤트_방송.clone()).await;
 
     let mut swarm = SwarmBuilder::new(transp, 처리_하자, *peer2peer::PEER_ID)
         .executor(Box::new(|fut| {
@@ -223,39 +804,74 @@ async fn main() {
         }))
         .build();
 
-    let mut stdin = BufReader::new(stdin()).lines();
+    let mut 명령_수신 = cli::입력_시작(cli::기록_경로(설정.데이터_디렉토리.as_deref()));
 
-    Swarm::listen_on(
-        &mut swarm,
-        "/ip4/0.0.0.0/tcp/0"
-            .parse()
-            .expect("로컬 소켓을 얻을 수 있음"),
-    )
-    .expect("swarm을 시작할 수 있음");
+    for 주소 in &설정.수신_주소들 {
+        if let Err(e) = Swarm::listen_on(&mut swarm, 주소.clone()) {
+            error!("{} 에서 수신할 수 없습니다: {}", 주소, e);
+            std::process::exit(1);
+        }
+    }
+    for 주소 in 설정.부트스트랩_주소들.clone() {
+        peer2peer::고정_피어_연결(&mut swarm, 주소);
+    }
+
+    if let Some(주소) = 설정.rpc_주소 {
+        if !주소.ip().is_loopback() {
+            warn!("JSON-RPC 에는 인증이 없습니다. {} 에 접근할 수 있는 누구나 블록을 채굴시키고 트랜잭션을 보낼 수 있습니다", 주소);
+        }
+        if let Err(e) = rpc::서버_시작(주소, rpc_송신자.clone()) {
+            error!("JSON-RPC 를 {} 에서 받을 수 없습니다: {}", 주소, e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(주소) = 설정.ws_주소 {
+        if let Err(e) = ws::서버_시작(주소, 이벤트_방송.clone()).await {
+            error!("WebSocket 구독을 {} 에서 받을 수 없습니다: {}", 주소, e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(주소) = 설정.explorer_주소 {
+        if let Err(e) = explorer::서버_시작(주소, rpc_송신자.clone()) {
+            error!("블록 탐색기 API 를 {} 에서 받을 수 없습니다: {}", 주소, e);
+            std::process::exit(1);
+        }
+    }
 
+    let 초기화_지연 = 설정.초기화_지연;
     spawn(async move {
-        sleep(Duration::from_secs(1)).await;
+        sleep(초기화_지연).await;
         info!("초기 이벤트 전송 중");
         초기_송신자.send(true).expect("초기 이벤트 전송 가능");
     });
-    //
-    let mut event = None;
+    let mut 동기화_타이머 = interval(Duration::from_secs(10));
+    // select! 는 준비된 가지 하나만 처리하고 끝나므로, 노드가 도는 동안 계속 이벤트를 받도록 loop 로 감쌉니다.
+    loop {
+        let mut event = None;
 
         select! {
-            라인 = stdin.next_line() => {
-                event = Some(peer2peer::이벤트_유형_열거형_데이타::Input(
-                    라인.expect("라인을 얻을 수 있음")
-                    .expect("stdin에서 라인을 읽을 수 있음")
-                ));
-            }
-            반응 = 반응_수신.recv() => {
-                event = Some(peer2peer::이벤트_유형_열거형_데이타::로컬_체인_반응(
-                    반응.expect("반응이 존재함")
-                ));
+            Some(명령) = 명령_수신.recv() => {
+                event = Some(peer2peer::이벤트_유형_열거형_데이타::Input(명령));
             }
             _초기 = 초기_수신.recv() => {
                 event = Some(peer2peer::이벤트_유형_열거형_데이타::Init);
             }
+            _ = 동기화_타이머.tick() => {
+                event = Some(peer2peer::이벤트_유형_열거형_데이타::동기화_점검);
+            }
+            Some(호출) = rpc_수신.recv() => {
+                event = Some(peer2peer::이벤트_유형_열거형_데이타::원격_호출(호출));
+            }
+            Some(체인_이벤트) = 체인_이벤트_수신.recv() => {
+                event = Some(peer2peer::이벤트_유형_열거형_데이타::체인_변경(체인_이벤트));
+            }
+            채굴된_블록 = 채굴_수신.recv() => {
+                event = Some(peer2peer::이벤트_유형_열거형_데이타::채굴_완료(
+                    채굴된_블록.expect("채굴 결과가 존재함")
+                ));
+            }
             event = swarm.select_next_some() => {
                 info!("처리되지 않은 Swarm Event: {:?}", event);
             }
@@ -264,42 +880,51 @@ async fn main() {
         if let Some(event) = event {
             match event {
                 peer2peer::이벤트_유형_열거형_데이타::Init => {
-                    let peers = peer2peer::peer_목록_얻기(&swarm);
+                    let peers = peer2peer::peer_목록_얻기(&mut swarm);
                     swarm.behaviour_mut().app.제네시스_함수();
 
                     info!("연결된 노드들: {}", peers.len());
                     if !peers.is_empty() {
-                        let req = peer2peer::로칼_체인_요청_구조체 {
-                            출처_peer_id: peers
-                                .iter()
-                                .last()
-                                .expect("at least one peer")
-                                .to_string(),
-                        };
-
-                        let json = serde_json::to_string(&req).expect("요청을 json화 할 수 있음");
-                        swarm
-                            .behaviour_mut()
-                            .floodsub
-                            .publish(peer2peer::CHAIN_TOPIC.clone(), json.as_bytes());
+                        // 피어들의 팁을 물어 더 무거운 체인이 있으면 헤더부터 받아 옵니다.
+                        swarm.behaviour_mut().팁_요청();
                     }
                 }
-                peer2peer::이벤트_유형_열거형_데이타::로컬_체인_반응(응답) => {
-                    let json = serde_json::to_string(&응답).expect("반응을 json 화 할 수 있음");
-                    swarm
-                        .behaviour_mut()
-                        .floodsub
-                        .publish(peer2peer::CHAIN_TOPIC.clone(), json.as_bytes());
+                peer2peer::이벤트_유형_열거형_데이타::동기화_점검 => {
+                    swarm.behaviour_mut().동기화_점검();
                 }
-                peer2peer::이벤트_유형_열거형_데이타::Input(라인) => match 라인.as_str() {
-                    "show peer" => peer2peer::연결된_peer_출력_함수(&swarm),
-                    cmd if cmd.starts_with("show chain") => peer2peer::체인_출력_처리_함수(&swarm),
-                    cmd if cmd.starts_with("new block") => peer2peer::새_블록_생성_처리_함수(cmd, &mut swarm),
-                    _ => error!("모르는 명령"),
+                peer2peer::이벤트_유형_열거형_데이타::체인_변경(체인_이벤트) => {
+                    peer2peer::체인_이벤트_출력_함수(&체인_이벤트);
+                    swarm.behaviour().이벤트_알림(ws::노드_이벤트::체인(체인_이벤트));
+                }
+                peer2peer::이벤트_유형_열거형_데이타::원격_호출(호출) => {
+                    rpc::호출_처리_함수(호출, &mut swarm);
+                }
+                peer2peer::이벤트_유형_열거형_데이타::채굴_완료(block) => {
+                    peer2peer::채굴된_블록_처리_함수(block, &mut swarm);
+                }
+                peer2peer::이벤트_유형_열거형_데이타::Input(명령) => match 명령 {
+                    cli::명령::도움말(대상) => cli::도움말_출력(대상),
+                    cli::명령::피어 => peer2peer::연결된_peer_출력_함수(&mut swarm),
+                    cli::명령::체인 => peer2peer::체인_출력_처리_함수(&swarm),
+                    cli::명령::채굴기 => peer2peer::채굴기_상태_출력_함수(&swarm),
+                    cli::명령::주소 => peer2peer::주소_출력_함수(),
+                    cli::명령::잔액(주소) => peer2peer::잔액_출력_함수(주소, &swarm),
+                    cli::명령::거래_풀 => peer2peer::거래_풀_출력_함수(&swarm),
+                    cli::명령::증명 { 블록_id, 인덱스 } => peer2peer::머클_증명_출력_함수(블록_id, 인덱스, &swarm),
+                    cli::명령::채굴(데이터) => peer2peer::새_블록_생성_처리_함수(데이터, &mut swarm),
+                    cli::명령::송금 { 수신자, 금액, 수수료 } => {
+                        peer2peer::새_트랜잭션_처리_함수(수신자, 금액, 수수료, &mut swarm)
+                    }
+                    cli::명령::연결(주소) => peer2peer::주소_연결(&mut swarm, 주소),
+                    cli::명령::종료 => {
+                        info!("노드를 끕니다");
+                        break;
+                    }
                 },
             }
         }
-
+        peer2peer::대기_연결_처리(&mut swarm);
+    }
 }
 
 
@@ -0,0 +1,209 @@
+use super::블록;
+use crate::{state::상태, transaction::트랜잭션};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+// 한 주소가 확정된 논스보다 이만큼 앞선 트랜잭션까지만 미리 받아 둡니다.
+const 최대_논스_간격: u64 = 16;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum 거래_거부_사유 {
+    중복,
+    서명_오류,
+    이미_사용된_논스 { 기대: u64, 실제: u64 },
+    논스_너무_앞섬 { 기대: u64, 실제: u64 },
+    같은_논스_수수료_낮음,
+    잔액_부족 { 필요: u64, 잔액: u64 },
+    풀_가득참,
+}
+
+impl fmt::Display for 거래_거부_사유 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            거래_거부_사유::중복 => write!(f, "이미 풀에 있는 트랜잭션입니다"),
+            거래_거부_사유::서명_오류 => write!(f, "서명이 올바르지 않습니다"),
+            거래_거부_사유::이미_사용된_논스 { 기대, 실제 } => {
+                write!(f, "논스 {} 는 이미 사용되었습니다 (다음 논스 {})", 실제, 기대)
+            }
+            거래_거부_사유::논스_너무_앞섬 { 기대, 실제 } => {
+                write!(f, "논스 {} 가 다음 논스 {} 보다 너무 앞서 있습니다", 실제, 기대)
+            }
+            거래_거부_사유::같은_논스_수수료_낮음 => {
+                write!(f, "같은 논스의 트랜잭션이 이미 있고 수수료가 더 높지 않습니다")
+            }
+            거래_거부_사유::잔액_부족 { 필요, 잔액 } => {
+                write!(f, "대기 중인 트랜잭션까지 합쳐 {} 가 필요하지만 잔액은 {} 입니다", 필요, 잔액)
+            }
+            거래_거부_사유::풀_가득참 => write!(f, "풀이 가득 찼고 수수료가 가장 낮은 트랜잭션보다 높지 않습니다"),
+        }
+    }
+}
+
+// 아직 블록에 실리지 않은 트랜잭션들을 해시로 보관합니다.
+// 같은 주소의 같은 논스는 하나만 두며, 수수료가 더 높은 트랜잭션이 오면 교체합니다.
+pub struct 거래_풀 {
+    거래들: HashMap<String, 트랜잭션>,
+    최대_개수: usize,
+}
+
+impl 거래_풀 {
+    pub fn new(최대_개수: usize) -> Self {
+        Self {
+            거래들: HashMap::new(),
+            최대_개수,
+        }
+    }
+
+    pub fn 개수(&self) -> usize {
+        self.거래들.len()
+    }
+
+    pub fn 수수료순_목록(&self) -> Vec<&트랜잭션> {
+        let mut 목록: Vec<&트랜잭션> = self.거래들.values().collect();
+        목록.sort_by(|a, b| b.수수료.cmp(&a.수수료).then_with(|| a.논스.cmp(&b.논스)));
+        목록
+    }
+
+    pub fn 추가(&mut self, 트랜잭션: 트랜잭션, 상태: &상태) -> Result<(), 거래_거부_사유> {
+        let 해시 = 트랜잭션.해시();
+        if self.거래들.contains_key(&해시) {
+            return Err(거래_거부_사유::중복);
+        }
+        if !트랜잭션.서명_확인() {
+            return Err(거래_거부_사유::서명_오류);
+        }
+
+        let 계정 = 상태.계정(&트랜잭션.송신자);
+        if 트랜잭션.논스 < 계정.논스 {
+            return Err(거래_거부_사유::이미_사용된_논스 {
+                기대: 계정.논스,
+                실제: 트랜잭션.논스,
+            });
+        }
+        if 트랜잭션.논스 > 계정.논스 + 최대_논스_간격 {
+            return Err(거래_거부_사유::논스_너무_앞섬 {
+                기대: 계정.논스,
+                실제: 트랜잭션.논스,
+            });
+        }
+
+        // 같은 송신자·논스의 기존 트랜잭션은 수수료가 더 높을 때만 교체합니다.
+        let 교체_대상 = self
+            .거래들
+            .iter()
+            .find(|(_, 기존)| 기존.송신자 == 트랜잭션.송신자 && 기존.논스 == 트랜잭션.논스)
+            .map(|(해시, 기존)| (해시.clone(), 기존.수수료));
+        if let Some((_, 기존_수수료)) = &교체_대상 {
+            if 트랜잭션.수수료 <= *기존_수수료 {
+                return Err(거래_거부_사유::같은_논스_수수료_낮음);
+            }
+        }
+
+        let 대기_중_지출: u64 = self
+            .거래들
+            .iter()
+            .filter(|(해시, 기존)| {
+                기존.송신자 == 트랜잭션.송신자
+                    && 교체_대상.as_ref().map_or(true, |(교체_해시, _)| 교체_해시 != *해시)
+            })
+            .map(|(_, 기존)| 기존.금액.saturating_add(기존.수수료))
+            .fold(0u64, u64::saturating_add);
+        let 필요 = 대기_중_지출
+            .saturating_add(트랜잭션.금액)
+            .saturating_add(트랜잭션.수수료);
+        if 필요 > 계정.잔액 {
+            return Err(거래_거부_사유::잔액_부족 {
+                필요,
+                잔액: 계정.잔액,
+            });
+        }
+
+        if let Some((교체_해시, _)) = 교체_대상 {
+            self.거래들.remove(&교체_해시);
+        } else if self.거래들.len() >= self.최대_개수 {
+            // 가득 찼으면 수수료가 가장 낮은 트랜잭션을 내보내고 그 자리를 씁니다.
+            let 최저 = self
+                .거래들
+                .iter()
+                .min_by_key(|(_, 기존)| 기존.수수료)
+                .map(|(해시, 기존)| (해시.clone(), 기존.수수료));
+            match 최저 {
+                Some((최저_해시, 최저_수수료)) if 트랜잭션.수수료 > 최저_수수료 => {
+                    self.거래들.remove(&최저_해시);
+                }
+                _ => return Err(거래_거부_사유::풀_가득참),
+            }
+        }
+
+        self.거래들.insert(해시, 트랜잭션);
+        Ok(())
+    }
+
+    // 수수료가 높은 순으로 고르되, 한 주소의 트랜잭션은 논스 순서를 지키고 잔액을 넘지 않게 고릅니다.
+    pub fn 블록_템플릿_선택(&self, 상태: &상태, 최대_개수: usize) -> Vec<트랜잭션> {
+        let mut 주소별: HashMap<&str, Vec<&트랜잭션>> = HashMap::new();
+        for 트랜잭션 in self.거래들.values() {
+            주소별.entry(트랜잭션.송신자.as_str()).or_default().push(트랜잭션);
+        }
+        for 목록 in 주소별.values_mut() {
+            목록.sort_by_key(|t| std::cmp::Reverse(t.논스));
+        }
+        let mut 남은_계정: HashMap<&str, (u64, u64)> = 주소별
+            .keys()
+            .map(|주소| {
+                let 계정 = 상태.계정(주소);
+                (*주소, (계정.잔액, 계정.논스))
+            })
+            .collect();
+
+        let mut 선택 = vec![];
+        while 선택.len() < 최대_개수 {
+            // 각 주소에서 다음 논스에 해당하는 트랜잭션 중 수수료가 가장 높은 것
+            let 후보 = 주소별
+                .iter()
+                .filter_map(|(주소, 목록)| {
+                    let 다음 = *목록.last()?;
+                    let (잔액, 논스) = 남은_계정[주소];
+                    let 필요 = 다음.금액.checked_add(다음.수수료)?;
+                    (다음.논스 == 논스 && 필요 <= 잔액).then(|| (*주소, 다음))
+                })
+                .max_by_key(|(_, t)| t.수수료);
+            let (주소, 트랜잭션) = match 후보 {
+                Some(후보) => 후보,
+                None => break,
+            };
+            let 계정 = 남은_계정.get_mut(주소).expect("주소별 계정이 있음");
+            계정.0 -= 트랜잭션.금액 + 트랜잭션.수수료;
+            계정.1 += 1;
+            주소별.get_mut(주소).expect("주소별 목록이 있음").pop();
+            선택.push(트랜잭션.clone());
+        }
+        선택
+    }
+
+    // 블록에 실린 트랜잭션을 빼고, 새 상태에서 더는 쓸 수 없는 트랜잭션도 정리합니다.
+    pub fn 블록_반영(&mut self, block: &블록, 상태: &상태) {
+        let 포함됨: HashSet<String> = block.트랜잭션들.iter().map(|t| t.해시()).collect();
+        self.거래들.retain(|해시, _| !포함됨.contains(해시));
+        self.정리(상태);
+    }
+
+    // 재구성으로 체인에서 떨어져 나온 트랜잭션들을 다시 받습니다. 새 상태에서 쓸 수 없는 것은
+    // 조용히 버리고, 다시 들어간 개수를 돌려줍니다.
+    pub fn 되돌리기(&mut self, 트랜잭션들: Vec<트랜잭션>, 상태: &상태) -> usize {
+        let mut 개수 = 0;
+        for 트랜잭션 in 트랜잭션들 {
+            if self.추가(트랜잭션, 상태).is_ok() {
+                개수 += 1;
+            }
+        }
+        개수
+    }
+
+    pub fn 정리(&mut self, 상태: &상태) {
+        self.거래들
+            .retain(|_, 트랜잭션| 트랜잭션.논스 >= 상태.계정(&트랜잭션.송신자).논스);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::transaction::트랜잭션;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// 잎과 내부 노드의 해시가 서로 같아질 수 없도록 앞에 붙이는 구분 바이트입니다.
+const 잎_접두사: u8 = 0x00;
+const 노드_접두사: u8 = 0x01;
+
+// 블록이 담는 항목 하나입니다. 0번 항목은 데이터이고, 그 뒤로 트랜잭션들이 순서대로 옵니다.
+pub enum 블록_항목<'a> {
+    데이터(&'a str),
+    트랜잭션(&'a 트랜잭션),
+}
+
+impl 블록_항목<'_> {
+    fn 바이트(&self) -> Vec<u8> {
+        match self {
+            블록_항목::데이터(데이터) => {
+                let mut 바이트 = vec![0u8];
+                바이트.extend_from_slice(데이터.as_bytes());
+                바이트
+            }
+            블록_항목::트랜잭션(트랜잭션) => {
+                let mut 바이트 = vec![1u8];
+                바이트.extend_from_slice(&트랜잭션.서명_대상());
+                바이트.extend_from_slice(트랜잭션.서명.as_bytes());
+                바이트
+            }
+        }
+    }
+
+    pub fn 잎_해시(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update([잎_접두사]);
+        hasher.update(self.바이트());
+        hasher.finalize().into()
+    }
+}
+
+fn 노드_해시(왼쪽: &[u8; 32], 오른쪽: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([노드_접두사]);
+    hasher.update(왼쪽);
+    hasher.update(오른쪽);
+    hasher.finalize().into()
+}
+
+// 한 단계 위로 올라갑니다. 짝이 없는 마지막 노드는 복제하지 않고 그대로 올려서
+// [a, b, c] 와 [a, b, c, c] 가 같은 루트를 갖는 문제를 피합니다.
+fn 윗단계(노드들: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    노드들
+        .chunks(2)
+        .map(|쌍| match 쌍 {
+            [왼쪽, 오른쪽] => 노드_해시(왼쪽, 오른쪽),
+            [혼자] => *혼자,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+pub fn 머클_루트(잎들: &[[u8; 32]]) -> [u8; 32] {
+    if 잎들.is_empty() {
+        return [0u8; 32];
+    }
+    let mut 단계 = 잎들.to_vec();
+    while 단계.len() > 1 {
+        단계 = 윗단계(&단계);
+    }
+    단계[0]
+}
+
+// 항목 하나가 루트에 포함되어 있음을 보이는 데 필요한 형제 노드들입니다.
+// 짝이 없어 그대로 올라간 단계에는 형제가 없으므로, 검증할 때 잎_개수로 그 단계를 건너뜁니다.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct 머클_증명 {
+    pub 인덱스: usize,
+    pub 잎_개수: usize,
+    pub 형제들: Vec<String>,
+}
+
+pub fn 증명_생성(잎들: &[[u8; 32]], 인덱스: usize) -> Option<머클_증명> {
+    if 인덱스 >= 잎들.len() {
+        return None;
+    }
+    let mut 형제들 = vec![];
+    let mut 단계 = 잎들.to_vec();
+    let mut 위치 = 인덱스;
+    while 단계.len() > 1 {
+        let 형제_위치 = 위치 ^ 1;
+        if 형제_위치 < 단계.len() {
+            형제들.push(hex::encode(단계[형제_위치]));
+        }
+        단계 = 윗단계(&단계);
+        위치 /= 2;
+    }
+    Some(머클_증명 {
+        인덱스,
+        잎_개수: 잎들.len(),
+        형제들,
+    })
+}
+
+pub fn 증명_검증(루트: &str, 잎_해시: [u8; 32], 증명: &머클_증명) -> bool {
+    if 증명.인덱스 >= 증명.잎_개수 {
+        return false;
+    }
+    let mut 현재 = 잎_해시;
+    let mut 위치 = 증명.인덱스;
+    let mut 폭 = 증명.잎_개수;
+    let mut 형제들 = 증명.형제들.iter();
+    while 폭 > 1 {
+        let 형제_위치 = 위치 ^ 1;
+        if 형제_위치 < 폭 {
+            let mut 형제 = [0u8; 32];
+            match 형제들.next().map(hex::decode) {
+                Some(Ok(바이트)) if 바이트.len() == 32 => 형제.copy_from_slice(&바이트),
+                _ => return false,
+            }
+            현재 = if 위치 % 2 == 0 {
+                노드_해시(&현재, &형제)
+            } else {
+                노드_해시(&형제, &현재)
+            };
+        }
+        위치 /= 2;
+        폭 = (폭 + 1) / 2;
+    }
+    형제들.next().is_none() && hex::encode(현재) == 루트
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn 잎들(개수: usize) -> Vec<[u8; 32]> {
+        (0..개수)
+            .map(|i| 블록_항목::데이터(&format!("항목 {}", i)).잎_해시())
+            .collect()
+    }
+
+    #[test]
+    fn 모든_잎의_증명이_검증됨() {
+        for 개수 in 1..=9 {
+            let 잎들 = 잎들(개수);
+            let 루트 = hex::encode(머클_루트(&잎들));
+            for (인덱스, 잎) in 잎들.iter().enumerate() {
+                let 증명 = 증명_생성(&잎들, 인덱스).unwrap();
+                assert!(증명_검증(&루트, *잎, &증명), "잎 {}개 중 {}", 개수, 인덱스);
+            }
+            assert_eq!(증명_생성(&잎들, 개수), None);
+        }
+    }
+
+    #[test]
+    fn 다른_잎은_검증되지_않음() {
+        let 잎들 = 잎들(4);
+        let 루트 = hex::encode(머클_루트(&잎들));
+        let 증명 = 증명_생성(&잎들, 1).unwrap();
+        assert!(!증명_검증(&루트, 잎들[2], &증명));
+        assert!(!증명_검증(&루트, 블록_항목::데이터("항목 1 ").잎_해시(), &증명));
+    }
+
+    #[test]
+    fn 다른_인덱스는_검증되지_않음() {
+        let 잎들 = 잎들(4);
+        let 루트 = hex::encode(머클_루트(&잎들));
+        let mut 증명 = 증명_생성(&잎들, 1).unwrap();
+        증명.인덱스 = 0;
+        assert!(!증명_검증(&루트, 잎들[1], &증명));
+        증명.인덱스 = 4;
+        assert!(!증명_검증(&루트, 잎들[1], &증명));
+    }
+
+    #[test]
+    fn 홀수_개_트리() {
+        let 잎들 = 잎들(5);
+        let 루트 = hex::encode(머클_루트(&잎들));
+        // 짝이 없는 마지막 잎은 형제 없이 올라가므로 증명이 짧습니다.
+        let 마지막 = 증명_생성(&잎들, 4).unwrap();
+        assert_eq!(마지막.형제들.len(), 1);
+        assert!(증명_검증(&루트, 잎들[4], &마지막));
+        assert!(!증명_검증(&루트, 잎들[3], &마지막));
+
+        // 마지막 잎을 복제한 트리와 루트가 달라야 합니다.
+        let mut 복제한_잎들 = 잎들.clone();
+        복제한_잎들.push(잎들[4]);
+        assert_ne!(머클_루트(&복제한_잎들), 머클_루트(&잎들));
+
+        // 잎_개수를 바꾸면 건너뛸 단계가 달라져 검증되지 않습니다.
+        let mut 틀린_개수 = 마지막.clone();
+        틀린_개수.잎_개수 = 6;
+        assert!(!증명_검증(&루트, 잎들[4], &틀린_개수));
+    }
+
+    #[test]
+    fn 형제가_남거나_모자라면_거부() {
+        let 잎들 = 잎들(3);
+        let 루트 = hex::encode(머클_루트(&잎들));
+        let mut 증명 = 증명_생성(&잎들, 0).unwrap();
+        증명.형제들.push(증명.형제들[0].clone());
+        assert!(!증명_검증(&루트, 잎들[0], &증명));
+        증명.형제들.truncate(1);
+        assert!(!증명_검증(&루트, 잎들[0], &증명));
+    }
+}
@@ -0,0 +1,177 @@
+use super::{해쉬_이진수_표현, 블록, 블록_헤더};
+use crate::{target::목표값, transaction::트랜잭션};
+use chrono::prelude::*;
+use log::{info, warn};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Instant,
+};
+use tokio::sync::mpsc;
+
+// 취소 여부와 해시 횟수를 이만큼의 시도마다 확인하고 더합니다.
+const 확인_간격: u64 = 1_000;
+// 해시레이트 로그를 남기는 간격(첫 번째 작업자 기준 시도 횟수)
+const 보고_간격: u64 = 250_000;
+
+// 채굴할 블록에서 논스와 해시를 뺀 나머지입니다.
+#[derive(Debug, Clone)]
+pub struct 채굴_템플릿 {
+    pub id: u64,
+    pub 이전_해시: String,
+    pub 데이터: String,
+    pub 트랜잭션들: Vec<트랜잭션>,
+    pub 머클_루트: String,
+    pub 채굴자: String,
+    pub 난이도_비트: u32,
+}
+
+struct 진행중_작업 {
+    템플릿: 채굴_템플릿,
+    취소: Arc<AtomicBool>,
+    해시_횟수: Arc<AtomicU64>,
+    시작_시각: Instant,
+}
+
+// 작업자 스레드 여러 개가 논스 공간을 나누어 탐색합니다.
+// 작업자 i 는 i, i + n, i + 2n ... 순서로 논스를 시도하고, 먼저 찾은 작업자가
+// 취소 플래그를 세워 나머지를 멈추게 한 뒤 완성된 블록을 결과 채널로 보냅니다.
+// tokio 런타임 스레드는 막지 않으므로 select! 루프는 채굴 중에도 계속 돌아갑니다.
+pub struct 채굴기 {
+    결과_송신자: mpsc::UnboundedSender<블록>,
+    작업자_수: usize,
+    현재_작업: Option<진행중_작업>,
+}
+
+impl 채굴기 {
+    pub fn new(결과_송신자: mpsc::UnboundedSender<블록>) -> Self {
+        let 작업자_수 = thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            결과_송신자,
+            작업자_수,
+            현재_작업: None,
+        }
+    }
+
+    // 진행 중인 작업이 있으면 취소하고 새 템플릿으로 채굴을 시작합니다.
+    pub fn 시작(&mut self, 템플릿: 채굴_템플릿) {
+        self.취소();
+        info!(
+            "블록 {} 채굴 시작 (작업자 {}개, 난이도 비트 {:#010x})",
+            템플릿.id, self.작업자_수, 템플릿.난이도_비트
+        );
+
+        let 취소 = Arc::new(AtomicBool::new(false));
+        let 해시_횟수 = Arc::new(AtomicU64::new(0));
+        let 시작_시각 = Instant::now();
+        let 타임스탬프 = Utc::now().timestamp();
+        let 목표 = match 목표값::압축에서(템플릿.난이도_비트) {
+            Some(목표) => 목표,
+            None => {
+                warn!("난이도 비트 {:#010x} 를 해석할 수 없어 채굴하지 않습니다", 템플릿.난이도_비트);
+                return;
+            }
+        };
+
+        for 번호 in 0..self.작업자_수 {
+            let 템플릿 = 템플릿.clone();
+            let 취소 = 취소.clone();
+            let 해시_횟수 = 해시_횟수.clone();
+            let 결과_송신자 = self.결과_송신자.clone();
+            let 간격 = self.작업자_수 as u64;
+            thread::spawn(move || {
+                let mut 헤더 = 블록_헤더 {
+                    id: 템플릿.id,
+                    타임스탬프,
+                    이전_해시: 템플릿.이전_해시.clone(),
+                    머클_루트: 템플릿.머클_루트.clone(),
+                    채굴자: 템플릿.채굴자.clone(),
+                    난이도_비트: 템플릿.난이도_비트,
+                    논스: 번호 as u64,
+                };
+                let mut 시도 = 0u64;
+                loop {
+                    let 해시 = 헤더.해시();
+                    if 목표.충족(&해시) {
+                        if !취소.swap(true, Ordering::SeqCst) {
+                            info!(
+                                "성공! 논스: {}, 해시: {}, binary 해시: {}",
+                                헤더.논스,
+                                hex::encode(&해시),
+                                해쉬_이진수_표현(&해시)
+                            );
+                            let _ = 결과_송신자.send(블록::조립(
+                                헤더,
+                                hex::encode(해시),
+                                템플릿.데이터,
+                                템플릿.트랜잭션들,
+                            ));
+                        }
+                        return;
+                    }
+
+                    시도 += 1;
+                    if 시도 % 확인_간격 == 0 {
+                        let 합계 = 해시_횟수.fetch_add(확인_간격, Ordering::Relaxed) + 확인_간격;
+                        if 취소.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        if 번호 == 0 && 시도 % 보고_간격 == 0 {
+                            let 초 = 시작_시각.elapsed().as_secs_f64().max(f64::EPSILON);
+                            info!("채굴 중... 논스: {}, 해시레이트: {:.0} H/s", 헤더.논스, 합계 as f64 / 초);
+                        }
+                    }
+                    헤더.논스 = match 헤더.논스.checked_add(간격) {
+                        Some(다음) => 다음,
+                        None => return,
+                    };
+                }
+            });
+        }
+
+        self.현재_작업 = Some(진행중_작업 {
+            템플릿,
+            취소,
+            해시_횟수,
+            시작_시각,
+        });
+    }
+
+    pub fn 취소(&mut self) {
+        if let Some(작업) = self.현재_작업.take() {
+            작업.취소.store(true, Ordering::SeqCst);
+            info!("블록 {} 채굴을 취소합니다", 작업.템플릿.id);
+        }
+    }
+
+    // 결과 채널로 블록을 받은 뒤 호출합니다. 이미 다른 작업으로 바뀌었다면 아무것도 하지 않습니다.
+    pub fn 완료(&mut self, 블록: &블록) {
+        let 같은_작업 = self
+            .현재_작업
+            .as_ref()
+            .map_or(false, |작업| 작업.템플릿.이전_해시 == 블록.이전_해시 && 작업.템플릿.id == 블록.id);
+        if 같은_작업 {
+            self.현재_작업 = None;
+        }
+    }
+
+    // 새 팁이 생겨 진행 중인 작업이 낡았을 때, 이전 템플릿의 내용으로 새 팁 위에서 다시 시작합니다.
+    pub fn 재시작(&mut self, 새_템플릿: impl FnOnce(&채굴_템플릿) -> 채굴_템플릿) {
+        if let Some(작업) = self.현재_작업.as_ref() {
+            let 템플릿 = 새_템플릿(&작업.템플릿);
+            info!("새 블록을 받아 블록 {} 위에서 채굴을 다시 시작합니다", 템플릿.id - 1);
+            self.시작(템플릿);
+        }
+    }
+
+    // 진행 중인 작업의 초당 해시 횟수입니다.
+    pub fn 해시레이트(&self) -> Option<f64> {
+        self.현재_작업.as_ref().map(|작업| {
+            let 초 = 작업.시작_시각.elapsed().as_secs_f64().max(f64::EPSILON);
+            작업.해시_횟수.load(Ordering::Relaxed) as f64 / 초
+        })
+    }
+}
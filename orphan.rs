@@ -0,0 +1,146 @@
+use super::블록;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// 풀에 담아 두는 최대 블록 수입니다. 가득 차면 가장 먼저 들어온 블록부터 버립니다.
+const 고아_풀_최대_개수: usize = 512;
+
+// 주 체인에 바로 붙일 수 없는 블록들입니다. 부모를 아직 받지 못한 고아 블록과,
+// 부모는 알지만 주 체인의 팁이 아닌 곳에서 갈라진 곁가지 블록이 함께 들어 있습니다.
+#[derive(Default)]
+pub struct 고아_풀 {
+    블록들: HashMap<String, 블록>,
+    순서: VecDeque<String>,
+}
+
+impl 고아_풀 {
+    pub fn 개수(&self) -> usize {
+        self.블록들.len()
+    }
+
+    pub fn 포함(&self, 해시: &str) -> bool {
+        self.블록들.contains_key(해시)
+    }
+
+    pub fn 가져오기(&self, 해시: &str) -> Option<&블록> {
+        self.블록들.get(해시)
+    }
+
+    pub fn 추가(&mut self, block: 블록) {
+        if self.포함(&block.해시) {
+            return;
+        }
+        while self.블록들.len() >= 고아_풀_최대_개수 {
+            match self.순서.pop_front() {
+                Some(오래된) => {
+                    self.블록들.remove(&오래된);
+                }
+                None => break,
+            }
+        }
+        self.순서.push_back(block.해시.clone());
+        self.블록들.insert(block.해시.clone(), block);
+    }
+
+    pub fn 제거(&mut self, 해시: &str) -> Option<블록> {
+        let block = self.블록들.remove(해시)?;
+        self.순서.retain(|h| h != 해시);
+        Some(block)
+    }
+
+    // 뿌리 블록과 풀 안의 그 자손들을 뺍니다. 풀에 있는 블록이 뿌리와 내용까지 같을 때만 빼서,
+    // 같은 해시의 다른 블록을 검증한 결과로 풀의 블록을 버리지 않습니다. 뺀 블록 수를 돌려줍니다.
+    pub fn 가지_제거(&mut self, 뿌리: &블록) -> usize {
+        if self.블록들.get(&뿌리.해시) != Some(뿌리) {
+            return 0;
+        }
+        let mut 뺄_해시들 = vec![뿌리.해시.clone()];
+        let mut 뺀_개수 = 0;
+        while let Some(해시) = 뺄_해시들.pop() {
+            뺄_해시들.extend(self.자식들(&해시));
+            if self.제거(&해시).is_some() {
+                뺀_개수 += 1;
+            }
+        }
+        뺀_개수
+    }
+
+    pub fn 자식들(&self, 부모_해시: &str) -> Vec<String> {
+        self.블록들
+            .values()
+            .filter(|b| b.이전_해시 == 부모_해시)
+            .map(|b| b.해시.clone())
+            .collect()
+    }
+
+    // 풀 안에 자식이 없는 블록들, 즉 곁가지들의 끝입니다.
+    pub fn 끝_해시들(&self) -> Vec<String> {
+        let 부모들: HashSet<&String> = self.블록들.values().map(|b| &b.이전_해시).collect();
+        self.블록들
+            .keys()
+            .filter(|해시| !부모들.contains(해시))
+            .cloned()
+            .collect()
+    }
+
+    // 끝 블록에서 부모를 따라 풀 안에서 갈 수 있는 데까지 거슬러 올라간 블록들입니다.
+    // 마지막 원소의 이전_해시가 풀 밖에 있는 분기점(또는 아직 받지 못한 부모)입니다.
+    pub fn 조상_경로(&self, 끝_해시: &str) -> Vec<&블록> {
+        let mut 경로 = vec![];
+        let mut 현재 = self.블록들.get(끝_해시);
+        while let Some(block) = 현재 {
+            경로.push(block);
+            // 잘못 만들어진 블록이 자기 자신을 부모로 가리켜도 멈추도록 풀 크기로 제한합니다.
+            if 경로.len() > self.블록들.len() {
+                break;
+            }
+            현재 = self.블록들.get(&block.이전_해시);
+        }
+        경로
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn 예시_블록(해시: &str, 이전_해시: &str) -> 블록 {
+        블록 {
+            id: 0,
+            타임스탬프: 0,
+            데이터: String::new(),
+            트랜잭션들: vec![],
+            머클_루트: String::new(),
+            채굴자: String::new(),
+            이전_해시: 이전_해시.to_string(),
+            해시: 해시.to_string(),
+            논스: 0,
+            난이도_비트: 0x207fffff,
+        }
+    }
+
+    #[test]
+    fn 가지_제거는_공통_조상을_남김() {
+        // 가 - 나 - 다 - 라
+        //        \ - 마
+        let mut 풀 = 고아_풀::default();
+        for (해시, 이전) in [("가", "주"), ("나", "가"), ("다", "나"), ("라", "다"), ("마", "나")] {
+            풀.추가(예시_블록(해시, 이전));
+        }
+
+        assert_eq!(풀.가지_제거(&예시_블록("다", "나")), 2);
+        assert!(풀.포함("가") && 풀.포함("나") && 풀.포함("마"));
+        assert!(!풀.포함("다") && !풀.포함("라"));
+        assert_eq!(풀.끝_해시들(), vec!["마".to_string()]);
+    }
+
+    #[test]
+    fn 가지_제거는_내용이_다르면_빼지_않음() {
+        let mut 풀 = 고아_풀::default();
+        풀.추가(예시_블록("가", "주"));
+        let mut 다른_본문 = 예시_블록("가", "주");
+        다른_본문.데이터 = "바뀐 데이터".to_string();
+
+        assert_eq!(풀.가지_제거(&다른_본문), 0);
+        assert!(풀.포함("가"));
+    }
+}
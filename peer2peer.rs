@@ -1,125 +1,478 @@
-use super::{앱, 블록};
+use super::{앱, 블록, 블록_수신_결과};
+use crate::{
+    cli, genesis, keyfile,
+    mempool::거래_거부_사유,
+    merkle,
+    miner::채굴기,
+    reorg::체인_이벤트,
+    protocol::{동기화_요청, 동기화_응답, 동기화_코덱, 동기화_프로토콜, 메시지, 인사_정보, 프로토콜_오류},
+    rpc,
+    sync::{self, 동기화, 보낼_요청, 팁_정보},
+    transaction::{self, 트랜잭션},
+    ws::노드_이벤트,
+};
 use libp2p::{
     NetworkBehaviour,
     identity,
+    kad::{record::store::MemoryStore, Kademlia, KademliaConfig, KademliaEvent, QueryResult},
     mdns::{Mdns, MdnsEvent},
+    multiaddr::Protocol,
     swarm::{
         NetworkBehaviourEventProcess, Swarm,
     },
-    PeerId,
-    floodsub::{Floodsub, FloodsubEvent, Topic},
+    Multiaddr, PeerId,
+    gossipsub::{
+        Gossipsub, GossipsubConfigBuilder, GossipsubEvent, GossipsubMessage, IdentTopic as Topic,
+        MessageAcceptance, MessageAuthenticity, MessageId, PeerScoreParams, PeerScoreThresholds,
+        TopicScoreParams, ValidationMode,
+    },
+    request_response::{
+        OutboundFailure, ProtocolSupport, RequestResponse, RequestResponseConfig, RequestResponseEvent,
+        RequestResponseMessage,
+    },
 };
 
 
-use log::{error, info};
+use log::{error, info, warn};
 use once_cell::sync::Lazy;
-use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use tokio::sync::mpsc;
-
-pub static KEYS: Lazy<identity::Keypair> = Lazy::new(identity::Keypair::generate_ed25519);
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashSet, VecDeque},
+    iter,
+    time::Duration,
+};
+use tokio::sync::{broadcast, mpsc};
+
+// 키 파일에서 읽어 재시작해도 PEER_ID 와 노드 주소가 그대로 유지됩니다.
+pub static KEYS: Lazy<identity::Keypair> = Lazy::new(|| {
+    let 경로 = keyfile::키_파일_경로();
+    keyfile::불러오기_또는_생성(&경로)
+        .unwrap_or_else(|e| panic!("노드 키 파일 {} 을 쓸 수 없음: {}", 경로.display(), e))
+});
 pub static PEER_ID: Lazy<PeerId> = Lazy::new(|| PeerId::from(KEYS.public()));
-pub static CHAIN_TOPIC: Lazy<Topic> = Lazy::new(|| Topic::new("chains"));
-pub static BLOCK_TOPIC: Lazy<Topic> = Lazy::new(|| Topic::new("블록들"));
+// 토픽 이름에 체인 ID 가 들어가 같은 LAN 에 있어도 다른 체인의 메시지는 받지 않습니다.
+pub static BLOCK_TOPIC: Lazy<Topic> = Lazy::new(|| Topic::new(genesis::이름공간("블록들")));
+pub static TX_TOPIC: Lazy<Topic> = Lazy::new(|| Topic::new(genesis::이름공간("트랜잭션들")));
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct 체인_반응_구조체 {
-    pub 블록들: Vec<블록>,
-    pub 수신자: String,
-}
+// 같은 메시지 ID 를 이 시간 동안 기억해 두 번 처리하거나 다시 퍼뜨리지 않습니다.
+const 중복_기억_시간: Duration = Duration::from_secs(120);
+// 유효하지 않은 블록이나 트랜잭션 하나마다 깎는 점수입니다. 두 번이면 graylist 기준(-80) 아래로 내려가
+// 그 피어의 메시지를 더 받지 않습니다.
+const 잘못된_메시지_가중치: f64 = -50.0;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct 로칼_체인_요청_구조체 {
-    pub 출처_peer_id: String,
-}
+// 동기화 요청에 이 시간 안에 응답이 없으면 실패로 보고 다른 피어에게 다시 요청합니다.
+const 동기화_요청_제한_시간: Duration = Duration::from_secs(15);
 
 pub enum 이벤트_유형_열거형_데이타 {
-    로컬_체인_반응(체인_반응_구조체),
-    Input(String),
+    채굴_완료(블록),
+    체인_변경(체인_이벤트),
+    동기화_점검,
+    원격_호출(rpc::대기_호출),
+    Input(cli::명령),
     Init,
 }
 
 #[derive(NetworkBehaviour)]
 pub struct 앱동작_구조체 {
-    pub floodsub: Floodsub,
+    pub gossipsub: Gossipsub,
     pub mdns: Mdns,
+    // 같은 서브넷 밖의 피어는 Kademlia 라우팅 테이블로 찾습니다.
+    pub kademlia: Kademlia<MemoryStore>,
+    pub 요청_응답: RequestResponse<동기화_코덱>,
     #[behaviour(ignore)]//컴파일러에게 해당 코드를 무시하도록 알려주는 것입니다. "behaviour"은 특정 기능을 정의하는 Rust 플러그인이고, "ignore"는 그 플러그인에서 정의한 기능 중 하나입니다.
-    pub 반응_송신자: mpsc::UnboundedSender<체인_반응_구조체>,
-    #[behaviour(ignore)]
     pub 초기_송신자: mpsc::UnboundedSender<bool>,
     #[behaviour(ignore)]
     pub app: 앱,
+    #[behaviour(ignore)]
+    pub 채굴기: 채굴기,
+    #[behaviour(ignore)]
+    pub 동기화: 동기화,
+    // WebSocket 구독자들에게 보낼 이벤트. 구독자가 없으면 버려집니다.
+    #[behaviour(ignore)]
+    pub 이벤트_방송: broadcast::Sender<노드_이벤트>,
+    // 인사를 보냈거나 받은 피어들
+    #[behaviour(ignore)]
+    인사한_피어들: HashSet<PeerId>,
+    // 인사에서 같은 체인임을 확인해 구독자에게 피어_발견 으로 알린 피어들
+    #[behaviour(ignore)]
+    확인된_피어들: HashSet<PeerId>,
+    // 다른 체인이거나 호환되지 않아 더는 상대하지 않는 피어들
+    #[behaviour(ignore)]
+    거부된_피어들: HashSet<PeerId>,
+    // 찾았지만 아직 연결하지 않은 피어들. behaviour 는 직접 dial 할 수 없으므로 이벤트 루프가 꺼내 연결합니다.
+    #[behaviour(ignore)]
+    연결할_피어들: VecDeque<PeerId>,
 }
 
 impl 앱동작_구조체 {
     pub async fn new(
         app: 앱,
-        반응_송신자: mpsc::UnboundedSender<체인_반응_구조체>,
         초기_송신자: mpsc::UnboundedSender<bool>,
+        채굴_송신자: mpsc::UnboundedSender<블록>,
+        이벤트_방송: broadcast::Sender<노드_이벤트>,
     ) -> Self {
+        let mut 요청_응답_설정 = RequestResponseConfig::default();
+        요청_응답_설정.set_request_timeout(동기화_요청_제한_시간);
+        let mut kademlia_설정 = KademliaConfig::default();
+        kademlia_설정.set_protocol_name(genesis::이름공간("kad/1.0.0").into_bytes());
         let mut behaviour = Self {
             app,
-            floodsub: Floodsub::new(*PEER_ID),
+            채굴기: 채굴기::new(채굴_송신자),
+            gossipsub: gossipsub_만들기(),
             mdns: Mdns::new(Default::default())
                 .await
                 .expect("mdns를 만들 수 없음"),
-            반응_송신자,
+            kademlia: Kademlia::with_config(
+                *PEER_ID,
+                MemoryStore::new(*PEER_ID),
+                kademlia_설정,
+            ),
+            요청_응답: RequestResponse::new(
+                동기화_코덱,
+                iter::once((동기화_프로토콜::현재(), ProtocolSupport::Full)),
+                요청_응답_설정,
+            ),
             초기_송신자,
+            동기화: 동기화::default(),
+            이벤트_방송,
+            인사한_피어들: HashSet::new(),
+            확인된_피어들: HashSet::new(),
+            거부된_피어들: HashSet::new(),
+            연결할_피어들: VecDeque::new(),
         };
-        // behaviour.floodsub.subscribe의 의미는 Floodsub 모듈의 subscribe 기능을 호출하고, 
+        // behaviour.gossipsub.subscribe의 의미는 Gossipsub 모듈의 subscribe 기능을 호출하고, 
         // 해당 기능을 통해 특정 토픽에 대한 구독을 수행하는 것을 의미합니다.이 구독은 libp2p 
         // 네트워크에 있는 다른 피어들에서 전달되는 해당 토픽에 대한 메시지를 받게 됩니다. 
-        // Floodsub 모듈은 libp2p 피어 간에 메시지를 퍼뜨리는 (flood) 프로토콜을 제공하며, 
-        // 이 구독 기능은 메시지를 받을 수 있는 방식을 제공합니다.
+        // Gossipsub 은 모두에게 퍼뜨리는 floodsub 과 달리 메시를 이룬 피어들에게만 전달하고, 
+        // 나머지 피어들에게는 받은 메시지의 ID 만 알려 필요한 쪽이 가져가게 합니다.
 
-        behaviour.floodsub.subscribe(CHAIN_TOPIC.clone());
-        behaviour.floodsub.subscribe(BLOCK_TOPIC.clone());
+        for 토픽 in [&*BLOCK_TOPIC, &*TX_TOPIC] {
+            behaviour
+                .gossipsub
+                .subscribe(토픽)
+                .expect("gossipsub 토픽을 구독할 수 있음");
+        }
 
         behaviour
     }
+
+    // 새 팁이 생겼으니 진행 중인 채굴이 있다면 새 팁 위에서 다시 시작합니다.
+    fn 채굴_재시작(&mut self) {
+        let app = &self.app;
+        self.채굴기.재시작(|이전| app.채굴_템플릿(이전.데이터.clone()));
+    }
+
+    // 받은 블록을 체인이나 고아 풀에 넣습니다. 부모를 모르면 보낸 피어에게 부모 블록을 요청하고,
+    // 그 블록도 부모를 모르면 같은 방식으로 한 단계씩 거슬러 올라갑니다.
+    fn 블록_처리(&mut self, 보낸_피어: PeerId, block: 블록) -> 블록_수신_결과 {
+        let 결과 = self.app.블록_수신_처리(block);
+        match &결과 {
+            블록_수신_결과::연결됨 => self.채굴_재시작(),
+            블록_수신_결과::부모_없음(부모_해시) => {
+                info!("{} 에게 부모 블록 {} 을 요청합니다", 보낸_피어, 부모_해시);
+                self.요청_응답.send_request(
+                    &보낸_피어,
+                    동기화_요청::블록들 {
+                        해시들: vec![부모_해시.clone()],
+                    },
+                );
+            }
+            블록_수신_결과::곁가지 | 블록_수신_결과::이미_있음 | 블록_수신_결과::거부됨 => {}
+        }
+        결과
+    }
+
+    // 현재 팁 위에 데이터와 거래 풀의 트랜잭션들을 담은 블록을 채굴하기 시작하고, 채굴할 블록의 id 를 돌려줍니다.
+    // 결과는 채굴_완료 이벤트로 돌아옵니다.
+    pub fn 채굴_시작(&mut self, 데이터: String) -> u64 {
+        let 템플릿 = self.app.채굴_템플릿(데이터);
+        info!("거래 풀의 트랜잭션 {}개를 담아 채굴합니다", 템플릿.트랜잭션들.len());
+        let id = 템플릿.id;
+        self.채굴기.시작(템플릿);
+        id
+    }
+
+    pub fn 이벤트_알림(&self, 이벤트: 노드_이벤트) {
+        // 받는 쪽이 하나도 없을 때만 실패하므로 무시합니다.
+        let _ = self.이벤트_방송.send(이벤트);
+    }
+
+    // 네트워크에서 받았거나 이 노드가 만든 트랜잭션을 거래 풀에 넣고 구독자에게 알립니다.
+    fn 거래_풀_추가(&mut self, 트랜잭션: 트랜잭션) -> Result<(), 거래_거부_사유> {
+        self.app.트랜잭션_추가(트랜잭션.clone())?;
+        self.이벤트_알림(노드_이벤트::거래_추가(트랜잭션));
+        Ok(())
+    }
+
+    // 거래 풀에 넣은 뒤 다른 노드들에게 퍼뜨립니다.
+    pub fn 트랜잭션_제출(&mut self, 트랜잭션: 트랜잭션) -> Result<(), 거래_거부_사유> {
+        let 해시 = 트랜잭션.해시();
+        self.거래_풀_추가(트랜잭션.clone())?;
+        info!("트랜잭션 {} 을 broadcast 합니다", 해시);
+        메시지_게시(&mut self.gossipsub, &메시지::새_트랜잭션(트랜잭션));
+        Ok(())
+    }
+
+    fn 요청_보내기(&mut self, 요청들: Vec<보낼_요청>) {
+        for (피어, 요청) in 요청들 {
+            let id = self.요청_응답.send_request(&피어, 요청.clone());
+            self.동기화.요청_보냄(id, 피어, &요청);
+        }
+    }
+
+    // mDNS 와 Kademlia 로 찾은 피어들 중 거부하지 않은 피어들입니다.
+    pub fn 알려진_피어들(&mut self) -> HashSet<PeerId> {
+        let mut 피어들: HashSet<PeerId> = self.mdns.discovered_nodes().cloned().collect();
+        for 버킷 in self.kademlia.kbuckets() {
+            for 항목 in 버킷.iter() {
+                피어들.insert(*항목.node.key.preimage());
+            }
+        }
+        피어들.retain(|피어| !self.거부된_피어들.contains(피어));
+        피어들
+    }
+
+    // mDNS, Kademlia, dial 로 찾은 피어의 주소를 등록하고 연결 대기열에 넣은 뒤 인사를 보냅니다.
+    // 연결되면 gossipsub 가 구독 정보를 주고받아 메시에 넣을지를 점수에 따라 정합니다.
+    fn 피어_추가(&mut self, 피어: PeerId, 주소: Multiaddr) {
+        if self.거부된_피어들.contains(&피어) {
+            return;
+        }
+        self.kademlia.add_address(&피어, 주소.clone());
+        self.요청_응답.add_address(&피어, 주소);
+        if 피어 != *PEER_ID {
+            self.연결할_피어들.push_back(피어);
+        }
+        self.인사_보내기(피어);
+    }
+
+    // 새로 찾은 피어에게 한 번 인사를 보내 같은 체인인지 확인합니다. 다른 체인의 노드는 프로토콜
+    // 이름이 달라 협상에서 실패하고, 그 실패도 거부 사유가 됩니다.
+    fn 인사_보내기(&mut self, 피어: PeerId) {
+        if 피어 == *PEER_ID || self.거부된_피어들.contains(&피어) || !self.인사한_피어들.insert(피어) {
+            return;
+        }
+        self.요청_응답
+            .send_request(&피어, 동기화_요청::인사(인사_정보::우리_노드()));
+    }
+
+    // 인사에서 체인 ID 가 맞는 것을 확인한 피어를 처음 한 번만 구독자에게 알립니다.
+    fn 피어_확인(&mut self, 피어: PeerId) {
+        if self.확인된_피어들.insert(피어) {
+            self.이벤트_알림(노드_이벤트::피어_발견(피어));
+        }
+    }
+
+    // 더는 쓰지 않을 피어를 잊습니다. 피어_발견 으로 알린 피어였다면 피어_만료 도 알립니다.
+    fn 피어_잊기(&mut self, 피어: PeerId) {
+        self.인사한_피어들.remove(&피어);
+        self.동기화.피어_제거(&피어);
+        if self.확인된_피어들.remove(&피어) {
+            self.이벤트_알림(노드_이벤트::피어_만료(피어));
+        }
+    }
+
+    fn 피어_거부(&mut self, 피어: PeerId, 사유: &str) {
+        if !self.거부된_피어들.insert(피어) {
+            return;
+        }
+        warn!("피어 {} 를 거부합니다: {}", 피어, 사유);
+        self.gossipsub.remove_explicit_peer(&피어);
+        self.gossipsub.blacklist_peer(&피어);
+        self.kademlia.remove_peer(&피어);
+        self.피어_잊기(피어);
+    }
+
+    // 라우팅 테이블의 피어들에게 우리와 가까운 피어를 물어 테이블을 채웁니다.
+    pub fn 피어_탐색(&mut self) {
+        if let Err(e) = self.kademlia.bootstrap() {
+            info!("Kademlia 피어 탐색을 시작할 수 없음: {:?}", e);
+        }
+    }
+
+    // 인사를 나눈 피어들에게 팁을 묻습니다. 답은 물은 노드에게만 오므로 gossipsub 로 퍼지지 않습니다.
+    pub fn 팁_요청(&mut self) {
+        let 피어들: Vec<PeerId> = self.인사한_피어들.difference(&self.거부된_피어들).cloned().collect();
+        for 피어 in 피어들 {
+            self.요청_응답.send_request(&피어, 동기화_요청::팁);
+        }
+    }
+
+    // 맡길 피어가 없어 미뤄 둔 동기화 요청을 다시 보내고, 동기화 중이 아니면 피어들의 팁을 다시 묻습니다.
+    pub fn 동기화_점검(&mut self) {
+        let 요청들 = self.동기화.점검(&self.app);
+        self.요청_보내기(요청들);
+        if !self.동기화.진행_중() {
+            self.팁_요청();
+        }
+    }
 }
 
 // 수신 이벤트 핸들러
 
 // NetworkBehaviourEventProcess 라는 모듈은 네트워크 동작 이벤트를 처리하는 모듈로서
 // libp2p::swarm::NetworkBehaviourEventProcess 를 통해 가져와 사용
-// Floodsub 모듈에서 inject_event 함수의 기능은 Floodsub 플러그인의 상태에 이벤트를 
+// Gossipsub 모듈에서 inject_event 함수의 기능은 Gossipsub 플러그인의 상태에 이벤트를 
 // 주입하는 것입니다. 
-// 이 함수는 Floodsub 플러그인의 상태를 업데이트하고, 이벤트에 대한 처리를 수행합니다. 
+// 이 함수는 Gossipsub 플러그인의 상태를 업데이트하고, 이벤트에 대한 처리를 수행합니다. 
 // 예를 들어, 새로운 구독에 대한 알림, 메시지 수신 등의 작업을 포함할 수 있습니다.
+//
+// 메시지는 검증 결과를 알려 주기 전까지 다른 피어에게 전달되지 않습니다. Accept 는 전달하고,
+// Ignore 는 전달하지 않고, Reject 는 전달하지 않으면서 보낸 피어의 점수를 깎습니다.
 
-impl NetworkBehaviourEventProcess<FloodsubEvent> for 앱동작_구조체 {
-    fn inject_event(&mut self, event: FloodsubEvent) {
-        // FloodsubEvent::Message인 경우에는 if, else if로 처리하면서, 
+impl NetworkBehaviourEventProcess<GossipsubEvent> for 앱동작_구조체 {
+    fn inject_event(&mut self, event: GossipsubEvent) {
+        // GossipsubEvent::Message인 경우에는 봉투를 열어 메시지 종류별로 처리하면서, 
         //나머지 경우에는 _로 처리하도록 했습니다. _는 모든 패턴에 매치되는 와일드카드 패턴입니다.
-        match event {
-            FloodsubEvent::Message(message) => {
-                if let Ok(response) = serde_json::from_slice::<체인_반응_구조체>(&message.data) {
-                    if response.수신자 == PEER_ID.to_string() {
-                        info!("{}에서의 응답:", message.source);
-                        response.블록들.iter().for_each(|r| info!("{:?}", r));
-        
-                        self.app.블록들 = self.app.체인_선택_함수(self.app.블록들.clone(), response.블록들);
+        if let GossipsubEvent::Message {
+            propagation_source,
+            message_id,
+            message,
+        } = event
+        {
+            // 처음 보낸 노드입니다. 서명을 요구하므로 항상 있지만, 없으면 전달해 준 피어로 대신합니다.
+            let 보낸_노드 = message.source.unwrap_or(propagation_source);
+            let 판정 = match 메시지::봉투_해석(&message.data) {
+                Ok((_, 메시지::새_트랜잭션(트랜잭션))) => {
+                    let 해시 = 트랜잭션.해시();
+                    match self.거래_풀_추가(트랜잭션) {
+                        Ok(()) => {
+                            info!("{} 에서 트랜잭션 {} 을 받아 풀에 추가함", 보낸_노드, 해시);
+                            MessageAcceptance::Accept
+                        }
+                        // 논스나 잔액 문제는 아직 우리가 모르는 블록 때문일 수 있으니 점수는 깎지 않습니다.
+                        Err(사유) => {
+                            info!("{} 의 트랜잭션 {} 을 받지 않음: {}", 보낸_노드, 해시, 사유);
+                            match 사유 {
+                                거래_거부_사유::서명_오류 => MessageAcceptance::Reject,
+                                _ => MessageAcceptance::Ignore,
+                            }
+                        }
                     }
-                } else if let Ok(response) = serde_json::from_slice::<로칼_체인_요청_구조체>(&message.data) {
-                    info!("로칼 체인을 {}에 보내는 중", message.source.to_string());
-                    let peer_id = response.출처_peer_id;
-                    if PEER_ID.to_string() == peer_id {
-                        if let Err(e) = self.반응_송신자.send(체인_반응_구조체 {
-                            블록들: self.app.블록들.clone(),
-                            수신자: message.source.to_string(),
-                        }) {
-                            error!("채널로 반응을 보내는데 에러발생, {}", e);
+                }
+                Ok((_, 메시지::새_블록(block))) => {
+                    info!("{} 에서 새로운 블록을 받음", 보낸_노드);
+                    // 부모 블록은 메시지를 전달해 준, 지금 연결된 피어에게 요청합니다.
+                    // 곁가지나 고아 블록은 헤더와 본문만 확인했을 뿐 체인에서 검증하지 않았으므로
+                    // 전달하지 않습니다. 다른 노드는 더 무거워진 곁가지를 동기화로 받아 갑니다.
+                    match self.블록_처리(propagation_source, block) {
+                        블록_수신_결과::연결됨 => MessageAcceptance::Accept,
+                        블록_수신_결과::곁가지 | 블록_수신_결과::부모_없음(_) | 블록_수신_결과::이미_있음 => {
+                            MessageAcceptance::Ignore
                         }
+                        블록_수신_결과::거부됨 => MessageAcceptance::Reject,
                     }
-                } else if let Ok(block) = serde_json::from_slice::<블록>(&message.data) {
-                    info!("{} 에서 새로운 블록을 받음", message.source.to_string());
-                    self.app.블록_추가시도_함수(block);
                 }
+                // 더 새로운 버전의 노드가 보낸 메시지일 수 있으니 경고 없이 넘어갑니다.
+                Err(e @ 프로토콜_오류::알_수_없는_메시지 { .. }) => {
+                    info!("{} 의 메시지를 무시함: {}", 보낸_노드, e);
+                    MessageAcceptance::Ignore
+                }
+                Err(e) => {
+                    warn!("{} 의 메시지를 버림: {}", 보낸_노드, e);
+                    MessageAcceptance::Reject
+                }
+            };
+            if let Err(e) =
+                self.gossipsub
+                    .report_message_validation_result(&message_id, &propagation_source, 판정)
+            {
+                warn!("메시지 {} 의 검증 결과를 알릴 수 없음: {:?}", message_id, e);
             }
-            _ => {}
         }
-        
+    }
+}
+
+// 헤더와 블록 요청은 요청한 피어에게만 응답하고, 받은 응답은 동기화에 넘깁니다.
+impl NetworkBehaviourEventProcess<RequestResponseEvent<동기화_요청, 동기화_응답>> for 앱동작_구조체 {
+    fn inject_event(&mut self, event: RequestResponseEvent<동기화_요청, 동기화_응답>) {
+        match event {
+            RequestResponseEvent::Message { peer, message } => match message {
+                // 거부한 피어의 요청에는 답하지 않고, 응답도 쓰지 않습니다.
+                RequestResponseMessage::Request { .. } | RequestResponseMessage::Response { .. }
+                    if self.거부된_피어들.contains(&peer) => {}
+                RequestResponseMessage::Request { request, channel, .. } => {
+                    let 응답 = match request {
+                        // 거부하더라도 상대가 이유를 알 수 있게 우리 정보는 돌려줍니다.
+                        동기화_요청::인사(상대) => {
+                            // 상대가 먼저 찾아 인사했으니 우리가 다시 인사할 필요는 없습니다.
+                            self.인사한_피어들.insert(peer);
+                            match 상대.호환_확인() {
+                                Ok(()) => self.피어_확인(peer),
+                                Err(사유) => self.피어_거부(peer, &사유),
+                            }
+                            동기화_응답::인사(인사_정보::우리_노드())
+                        }
+                        동기화_요청::팁 => 동기화_응답::팁(팁_정보::체인에서(&self.app.블록들)),
+                        동기화_요청::헤더들 { 로케이터 } => {
+                            동기화_응답::헤더들(sync::헤더_찾기(&self.app.블록들, &로케이터))
+                        }
+                        동기화_요청::블록들 { 해시들 } => {
+                            let mut 블록들 = sync::블록_찾기(&self.app.블록들, &해시들);
+                            // 고아 블록의 부모를 찾는 요청은 우리 곁가지에 있는 블록을 찾을 수도 있습니다.
+                            for 해시 in 해시들.iter().take(sync::본문_묶음_크기) {
+                                if let Some(block) = self.app.고아_풀.가져오기(해시) {
+                                    블록들.push(block.clone());
+                                }
+                            }
+                            동기화_응답::블록들(블록들)
+                        }
+                    };
+                    if self.요청_응답.send_response(channel, 응답).is_err() {
+                        warn!("{} 와의 연결이 끊겨 동기화 응답을 보내지 못함", peer);
+                    }
+                }
+                RequestResponseMessage::Response { request_id, response } => {
+                    let 요청들 = match response {
+                        동기화_응답::인사(상대) => {
+                            match 상대.호환_확인() {
+                                Ok(()) => {
+                                    info!("피어 {} 와 인사함 (체인 {})", peer, 상대.체인_id);
+                                    self.피어_확인(peer);
+                                    self.요청_응답.send_request(&peer, 동기화_요청::팁);
+                                }
+                                Err(사유) => self.피어_거부(peer, &사유),
+                            }
+                            vec![]
+                        }
+                        동기화_응답::팁(팁) => self.동기화.팁_수신(peer, 팁, &self.app),
+                        동기화_응답::헤더들(헤더들) => self.동기화.헤더_수신(peer, 헤더들, &self.app),
+                        동기화_응답::블록들(블록들) => {
+                            // 동기화 중인 헤더와 맞지 않는 블록은 고아 블록의 부모를 요청해 받은 것입니다.
+                            let (동기화_블록들, 고아_조상들): (Vec<_>, Vec<_>) =
+                                블록들.into_iter().partition(|b| self.동기화.기다리는_블록(b));
+                            for block in 고아_조상들 {
+                                self.블록_처리(peer, block);
+                            }
+                            let (요청들, 팁_바뀜) =
+                                self.동기화.블록_수신(request_id, peer, 동기화_블록들, &mut self.app);
+                            if 팁_바뀜 {
+                                self.app.고아_처리();
+                                self.채굴_재시작();
+                            }
+                            요청들
+                        }
+                    };
+                    self.요청_보내기(요청들);
+                }
+            },
+            RequestResponseEvent::OutboundFailure {
+                peer,
+                error: OutboundFailure::UnsupportedProtocols,
+                ..
+            } => {
+                self.피어_거부(peer, "동기화 프로토콜이 다릅니다 (다른 체인이거나 호환되지 않는 버전)");
+            }
+            RequestResponseEvent::OutboundFailure { peer, error, .. } => {
+                info!("{} 에게 보낸 동기화 요청 실패: {:?}", peer, error);
+                let 요청들 = self.동기화.요청_실패(&peer, &self.app);
+                self.요청_보내기(요청들);
+            }
+            RequestResponseEvent::InboundFailure { peer, error, .. } => {
+                warn!("{} 의 동기화 요청을 처리하지 못함: {:?}", peer, error);
+            }
+            RequestResponseEvent::ResponseSent { .. } => {}
+        }
     }
 }
 
@@ -127,14 +480,19 @@ impl NetworkBehaviourEventProcess<MdnsEvent> for 앱동작_구조체 {
     fn inject_event(&mut self, event: MdnsEvent) {
         match event {
             MdnsEvent::Discovered(발견된_노드_목록) => {
-                for (peer, _addr) in 발견된_노드_목록 {
-                    self.floodsub.add_node_to_partial_view(peer);
+                for (peer, addr) in 발견된_노드_목록 {
+                    self.피어_추가(peer, addr);
                 }
             }
             MdnsEvent::Expired(만료된_노드_목록) => {
-                for (peer, _addr) in 만료된_노드_목록 {
-                    if !self.mdns.has_node(&peer) {
-                        self.floodsub.remove_node_from_partial_view(&peer);
+                for (peer, addr) in 만료된_노드_목록 {
+                    self.요청_응답.remove_address(&peer, &addr);
+                    if self.mdns.has_node(&peer) {
+                        continue;
+                    }
+                    self.kademlia.remove_address(&peer, &addr);
+                    if !self.알려진_피어들().contains(&peer) {
+                        self.피어_잊기(peer);
                     }
                 }
             }
@@ -142,23 +500,148 @@ impl NetworkBehaviourEventProcess<MdnsEvent> for 앱동작_구조체 {
     }
 }
 
-pub fn peer_목록_얻기(swarm: &Swarm<앱동작_구조체>) -> Vec<String> {
-    info!("발견된 피어들:");
-    let nodes = swarm.behaviour().mdns.discovered_nodes();//네트워크에서 찾은 노드 목록을 nodes 변수에 할당
-    let mut unique_peers = HashSet::new();
-    for peer in nodes {
-        unique_peers.insert(peer);
+// Kademlia 가 라우팅 테이블에 넣은 피어도 mDNS 로 찾은 피어처럼 인사한 뒤 gossipsub 와 동기화 요청에 씁니다.
+impl NetworkBehaviourEventProcess<KademliaEvent> for 앱동작_구조체 {
+    fn inject_event(&mut self, event: KademliaEvent) {
+        match event {
+            KademliaEvent::RoutingUpdated {
+                peer,
+                is_new_peer,
+                addresses,
+                ..
+            } => {
+                if is_new_peer {
+                    info!("Kademlia 로 피어 {} 를 찾음", peer);
+                }
+                for addr in addresses.iter() {
+                    self.피어_추가(peer, addr.clone());
+                }
+            }
+            KademliaEvent::OutboundQueryCompleted {
+                result: QueryResult::Bootstrap(결과),
+                ..
+            } => match 결과 {
+                Ok(_) => info!("Kademlia 피어 탐색 완료"),
+                Err(e) => warn!("Kademlia 피어 탐색 실패: {:?}", e),
+            },
+            _ => {}
+        }
     }
-    unique_peers.iter().map(|p| p.to_string()).collect()
+}
+
+// 메시지를 버전과 ID 가 붙은 봉투에 담아 종류에 맞는 토픽으로 퍼뜨립니다.
+// 연결된 피어가 없거나 이미 퍼진 메시지면 실패하지만, 다음 동기화 때 다시 맞춰지므로 로그만 남깁니다.
+pub fn 메시지_게시(gossipsub: &mut Gossipsub, 메시지: &메시지) {
+    if let Err(e) = gossipsub.publish(메시지.토픽(), 메시지.봉투_인코딩()) {
+        info!("메시지를 퍼뜨리지 못함: {:?}", e);
+    }
+}
+
+// 블록과 트랜잭션은 내용이 같으면 누가 보냈든 같은 메시지로 보고 한 번만 처리합니다.
+fn 메시지_id(message: &GossipsubMessage) -> MessageId {
+    let mut 해셔 = Sha256::new();
+    해셔.update(&message.data);
+    MessageId::from(hex::encode(해셔.finalize()))
+}
+
+// 받은 메시지는 앱에서 검증한 뒤에만 전달하고, 블록·트랜잭션 토픽에서 잘못된 메시지를 보낸
+// 피어는 점수를 깎아 graylist 기준 아래로 내려가면 더는 메시지를 받지 않습니다.
+fn gossipsub_만들기() -> Gossipsub {
+    let 설정 = GossipsubConfigBuilder::default()
+        .validation_mode(ValidationMode::Strict)
+        .validate_messages()
+        .message_id_fn(메시지_id)
+        .duplicate_cache_time(중복_기억_시간)
+        .build()
+        .expect("gossipsub 설정이 올바름");
+    let mut gossipsub = Gossipsub::new(MessageAuthenticity::Signed(KEYS.clone()), 설정)
+        .expect("gossipsub 를 만들 수 있음");
+
+    let mut 점수_설정 = PeerScoreParams::default();
+    for 토픽 in [&*BLOCK_TOPIC, &*TX_TOPIC] {
+        점수_설정.topics.insert(
+            토픽.hash(),
+            TopicScoreParams {
+                topic_weight: 1.0,
+                invalid_message_deliveries_weight: 잘못된_메시지_가중치,
+                // 블록은 드물게 오므로 메시에서 메시지가 적다는 이유로는 점수를 깎지 않습니다.
+                mesh_message_deliveries_weight: 0.0,
+                mesh_failure_penalty_weight: 0.0,
+                ..TopicScoreParams::default()
+            },
+        );
+    }
+    gossipsub
+        .with_peer_score(점수_설정, PeerScoreThresholds::default())
+        .expect("피어 점수 설정이 올바름");
+    gossipsub
+}
+
+pub fn peer_목록_얻기(swarm: &mut Swarm<앱동작_구조체>) -> Vec<String> {
+    info!("발견된 피어들:");
+    let nodes = swarm.behaviour_mut().알려진_피어들();//mDNS 와 Kademlia 로 찾은 노드 목록을 nodes 변수에 할당
+    nodes.iter().map(|p| p.to_string()).collect()
 }
 
 //아래의 rust 함수는 Swarm<앱동작_구조체> 타입의 참조자 swarm을 인자로 받아, peer 목록을 얻어와 각 peer를 출력하는 함수입니다.
-pub fn 연결된_peer_출력_함수(swarm: &Swarm<앱동작_구조체>) {
-    let peers = peer_목록_얻기(swarm);
-    peers.iter().for_each(|p| info!("{}", p));
+// gossipsub 가 매긴 점수도 함께 출력합니다. 잘못된 블록이나 트랜잭션을 보낸 피어는 음수가 됩니다.
+pub fn 연결된_peer_출력_함수(swarm: &mut Swarm<앱동작_구조체>) {
+    info!("발견된 피어들:");
+    let behaviour = swarm.behaviour_mut();
+    for peer in behaviour.알려진_피어들() {
+        match behaviour.gossipsub.peer_score(&peer) {
+            Some(점수) => info!("{} (점수 {:.1})", peer, 점수),
+            None => info!("{}", peer),
+        }
+    }
+}
+
+
+
+// 주소 끝에 /p2p/<피어 ID> 가 있으면 그 피어 ID 입니다.
+fn 주소의_피어(주소: &Multiaddr) -> Option<PeerId> {
+    match 주소.iter().last() {
+        Some(Protocol::P2p(해시)) => PeerId::from_multihash(해시).ok(),
+        _ => None,
+    }
 }
 
+// 부트스트랩 목록이나 dial 명령으로 받은 주소에 연결합니다. 피어 ID 가 붙어 있으면 Kademlia 라우팅
+// 테이블에도 넣어, 연결된 뒤 그 피어를 통해 다른 서브넷의 피어들을 찾을 수 있게 합니다.
+pub fn 주소_연결(swarm: &mut Swarm<앱동작_구조체>, 주소: Multiaddr) {
+    if let Some(peer) = 주소의_피어(&주소) {
+        swarm.behaviour_mut().피어_추가(peer, 주소.clone());
+        // 아래에서 이 주소로 바로 연결하므로 대기열에서 다시 연결하지 않습니다.
+        swarm.behaviour_mut().연결할_피어들.retain(|p| *p != peer);
+    }
+    match swarm.dial_addr(주소.clone()) {
+        Ok(()) => info!("{} 에 연결합니다", 주소),
+        Err(e) => error!("{} 에 연결할 수 없습니다: {:?}", 주소, e),
+    }
+    swarm.behaviour_mut().피어_탐색();
+}
+
+// 설정 파일이나 명령줄로 받은 고정 피어에 연결합니다. 고정 피어는 gossipsub 의 explicit peer 로
+// 두어 메시 상태나 점수와 관계없이 항상 메시지를 주고받습니다.
+pub fn 고정_피어_연결(swarm: &mut Swarm<앱동작_구조체>, 주소: Multiaddr) {
+    match 주소의_피어(&주소) {
+        Some(peer) => swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer),
+        None => warn!("고정 피어 주소 {} 에 피어 ID 가 없어 일반 피어로 연결합니다", 주소),
+    }
+    주소_연결(swarm, 주소);
+}
 
+// 피어_추가 가 대기열에 넣은 피어들 중 아직 연결되지 않은 피어에 연결합니다.
+pub fn 대기_연결_처리(swarm: &mut Swarm<앱동작_구조체>) {
+    while let Some(peer) = swarm.behaviour_mut().연결할_피어들.pop_front() {
+        if Swarm::is_connected(swarm, &peer) || swarm.behaviour().거부된_피어들.contains(&peer) {
+            continue;
+        }
+        if let Err(e) = swarm.dial(&peer) {
+            info!("{} 에 연결할 수 없습니다: {:?}", peer, e);
+        }
+    }
+}
 
 pub fn 체인_출력_처리_함수(swarm: &Swarm<앱동작_구조체>) {
     info!("로컬 블록체인:");
@@ -166,30 +649,131 @@ pub fn 체인_출력_처리_함수(swarm: &Swarm<앱동작_구조체>) {
         serde_json::to_string_pretty(&swarm.behaviour().app.블록들).expect("블록들을 json으로 변환할 수 있음");
     info!("{}", 블록_json);
 }
-//
-pub fn 새_블록_생성_처리_함수(cmd: &str, swarm: &mut Swarm<앱동작_구조체>) {
-    match cmd.strip_prefix("new block") {
-        Some(데이터) => {
-            let behaviour = swarm.behaviour_mut();
-            let 마지막_블록 = behaviour
-                .app
-                .블록들
-                .last()
-                .expect("적어도 하나의 블록이 있어야 합니다");
-            let block = 블록::new(
-                마지막_블록.id + 1,
-                마지막_블록.해시.clone(),
-                데이터.to_owned(),
+// 주 체인에 블록이 붙거나 재구성으로 떨어질 때마다 남기는 로그입니다.
+pub fn 체인_이벤트_출력_함수(이벤트: &체인_이벤트) {
+    match 이벤트 {
+        체인_이벤트::연결됨(block) => info!("블록 {} 연결됨: {}", block.id, block.해시),
+        체인_이벤트::해제됨(block) => warn!("블록 {} 해제됨: {}", block.id, block.해시),
+        체인_이벤트::재구성됨 { 해제된, 연결된 } => {
+            warn!("재구성: 블록 {}개를 떼어 내고 {}개를 붙였습니다", 해제된.len(), 연결된.len())
+        }
+    }
+}
+
+pub fn 채굴기_상태_출력_함수(swarm: &Swarm<앱동작_구조체>) {
+    match swarm.behaviour().채굴기.해시레이트() {
+        Some(해시레이트) => info!("채굴 중, 해시레이트: {:.0} H/s", 해시레이트),
+        None => info!("채굴 중이 아님"),
+    }
+}
+
+// 0번 항목은 데이터, 1번부터는 트랜잭션입니다.
+pub fn 머클_증명_출력_함수(블록_id: u64, 인덱스: usize, swarm: &Swarm<앱동작_구조체>) {
+    let block = match swarm.behaviour().app.블록들.iter().find(|b| b.id == 블록_id) {
+        Some(block) => block,
+        None => {
+            error!("id: {} 인 블록이 없습니다", 블록_id);
+            return;
+        }
+    };
+    match block.머클_증명(인덱스) {
+        Some((증명, 잎_해시)) => {
+            let 확인 = merkle::증명_검증(&block.머클_루트, 잎_해시, &증명);
+            info!(
+                "블록 {} 항목 {} 의 머클 증명 (검증 {}): {}",
+                블록_id,
+                인덱스,
+                if 확인 { "성공" } else { "실패" },
+                serde_json::to_string(&증명).expect("증명을 json으로 변환할 수 있음")
             );
+        }
+        None => error!("블록 {} 에는 {}번 항목이 없습니다", 블록_id, 인덱스),
+    }
+}
 
-            let json = serde_json::to_string(&block).expect("블록들을 json으로 변환할 수 있음");
-            behaviour.app.블록들.push(block);
+pub fn 거래_풀_출력_함수(swarm: &Swarm<앱동작_구조체>) {
+    let 풀 = &swarm.behaviour().app.거래_풀;
+    info!("거래 풀: {}개", 풀.개수());
+    풀.수수료순_목록().iter().for_each(|t| {
+        info!(
+            "{} 수수료 {} 금액 {} 논스 {} {} -> {}",
+            t.해시(),
+            t.수수료,
+            t.금액,
+            t.논스,
+            t.송신자,
+            t.수신자
+        )
+    });
+}
+//
+// 채굴은 작업자 스레드에서 진행되고, 결과는 채굴_완료 이벤트로 돌아옵니다.
+pub fn 새_블록_생성_처리_함수(데이터: String, swarm: &mut Swarm<앱동작_구조체>) {
+    swarm.behaviour_mut().채굴_시작(데이터);
+}
+
+// 이 노드의 ed25519 키입니다. 노드 식별과 트랜잭션 서명에 같은 키를 씁니다.
+fn 노드_서명_키() -> &'static identity::ed25519::Keypair {
+    match &*KEYS {
+        identity::Keypair::Ed25519(키) => 키,
+        _ => panic!("노드 키는 ed25519 여야 합니다"),
+    }
+}
+
+pub fn 노드_주소() -> String {
+    transaction::주소(&노드_서명_키().public())
+}
+
+pub fn 주소_출력_함수() {
+    info!("내 주소: {}", 노드_주소());
+}
+
+// 주소를 생략하면 이 노드의 주소를 조회합니다.
+pub fn 잔액_출력_함수(주소: Option<String>, swarm: &Swarm<앱동작_구조체>) {
+    let 주소 = 주소.unwrap_or_else(노드_주소);
+    let app = &swarm.behaviour().app;
+    info!("{} 의 잔액: {} (다음 논스 {})", 주소, app.잔액(&주소), app.다음_논스(&주소));
+}
+//
+// 노드 키로 서명한 트랜잭션을 거래 풀에 넣고 다른 노드들에게 퍼뜨립니다.
+// 블록에 실리려면 누군가 mine 으로 채굴해야 합니다.
+pub fn 새_트랜잭션_처리_함수(수신자: String, 금액: u64, 수수료: u64, swarm: &mut Swarm<앱동작_구조체>) {
+    let 키 = 노드_서명_키();
+    let behaviour = swarm.behaviour_mut();
+    let 내_주소 = 노드_주소();
+    // 풀에서 기다리는 내 트랜잭션들 다음 논스를 씁니다.
+    let 대기_중 = behaviour
+        .app
+        .거래_풀
+        .수수료순_목록()
+        .iter()
+        .filter(|t| t.송신자 == 내_주소)
+        .count() as u64;
+    let 논스 = behaviour.app.다음_논스(&내_주소) + 대기_중;
+    let 트랜잭션 = 트랜잭션::서명하여_생성(키, 수신자.clone(), 금액, 논스, 수수료);
+    if !트랜잭션.서명_확인() {
+        error!("수신자 주소가 올바르지 않습니다: {}", 수신자);
+        return;
+    }
+    if let Err(사유) = behaviour.트랜잭션_제출(트랜잭션) {
+        error!("트랜잭션을 만들 수 없습니다: {}", 사유);
+    }
+}
+//
+pub fn 채굴된_블록_처리_함수(block: 블록, swarm: &mut Swarm<앱동작_구조체>) {
+    let behaviour = swarm.behaviour_mut();
+    behaviour.채굴기.완료(&block);
+    match behaviour.app.블록_수신_처리(block.clone()) {
+        블록_수신_결과::연결됨 => {
             info!("새 블록을 broadcast 합니다");
-            behaviour
-                .floodsub
-                .publish(BLOCK_TOPIC.clone(), json.as_bytes());
-        },
-        None => {},
+            메시지_게시(&mut behaviour.gossipsub, &메시지::새_블록(block));
+        }
+        // 채굴하는 사이 팁이 바뀌었습니다. 곁가지라도 다른 노드가 더 무거운 가지를 만들 수 있게 퍼뜨립니다.
+        블록_수신_결과::곁가지 => {
+            info!("채굴한 블록이 곁가지가 되었지만 broadcast 합니다");
+            메시지_게시(&mut behaviour.gossipsub, &메시지::새_블록(block));
+        }
+        _ => {}
     }
 }
 
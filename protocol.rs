@@ -0,0 +1,328 @@
+use super::{블록, 블록_헤더};
+use crate::{
+    codec::{self, 디코딩_오류, 이진_인코딩, 읽기_커서},
+    genesis,
+    peer2peer::{BLOCK_TOPIC, TX_TOPIC},
+    sync::팁_정보,
+    transaction::트랜잭션,
+};
+use async_trait::async_trait;
+use libp2p::{
+    core::upgrade::{read_length_prefixed, write_length_prefixed, ProtocolName},
+    gossipsub::IdentTopic as Topic,
+    futures::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    request_response::RequestResponseCodec,
+};
+use std::{fmt, io};
+
+// 이 노드가 보내는 메시지의 프로토콜 버전입니다.
+pub const 프로토콜_버전: u16 = 1;
+// 이보다 낮은 버전의 메시지는 해석하지 않습니다.
+pub const 최소_호환_버전: u16 = 1;
+
+// 메시지 종류마다 고정된 번호입니다. 한 번 정한 번호와 본문 배치는 바꾸지 않고,
+// 새 메시지는 새 번호로 추가합니다.
+const 새_블록_ID: u8 = 1;
+const 새_트랜잭션_ID: u8 = 2;
+
+// 봉투 = 프로토콜 버전 u16 | 메시지 ID u8 | 본문 (u32 길이 접두사)
+//
+// 호환 규칙
+// - 최소_호환_버전보다 낮은 버전은 거부합니다.
+// - 모르는 메시지 ID 는 더 새로운 노드가 보낸 것으로 보고 로그만 남긴 채 무시합니다.
+// - 더 높은 버전의 노드는 아는 메시지의 본문 끝에 필드를 덧붙일 수 있고, 낮은 버전의 노드는
+//   자신이 아는 부분까지만 읽습니다. 같은 버전 이하라면 본문 끝에 남는 바이트는 오류입니다.
+#[derive(Debug)]
+pub enum 메시지 {
+    새_블록(블록),
+    새_트랜잭션(트랜잭션),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum 프로토콜_오류 {
+    호환되지_않는_버전(u16),
+    알_수_없는_메시지 { 버전: u16, id: u8 },
+    디코딩(디코딩_오류),
+}
+
+impl From<디코딩_오류> for 프로토콜_오류 {
+    fn from(e: 디코딩_오류) -> Self {
+        프로토콜_오류::디코딩(e)
+    }
+}
+
+impl fmt::Display for 프로토콜_오류 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            프로토콜_오류::호환되지_않는_버전(버전) => write!(
+                f,
+                "프로토콜 버전 {} 는 최소 호환 버전 {} 보다 낮습니다",
+                버전, 최소_호환_버전
+            ),
+            프로토콜_오류::알_수_없는_메시지 { 버전, id } => {
+                write!(f, "프로토콜 버전 {} 의 메시지 ID {} 를 알지 못합니다", 버전, id)
+            }
+            프로토콜_오류::디코딩(e) => write!(f, "본문을 해석할 수 없습니다: {}", e),
+        }
+    }
+}
+
+impl 메시지 {
+    fn id(&self) -> u8 {
+        match self {
+            메시지::새_블록(_) => 새_블록_ID,
+            메시지::새_트랜잭션(_) => 새_트랜잭션_ID,
+        }
+    }
+
+    // 메시지를 퍼뜨릴 gossipsub 토픽입니다.
+    pub fn 토픽(&self) -> Topic {
+        match self {
+            메시지::새_블록(_) => BLOCK_TOPIC.clone(),
+            메시지::새_트랜잭션(_) => TX_TOPIC.clone(),
+        }
+    }
+
+    pub fn 봉투_인코딩(&self) -> Vec<u8> {
+        let 본문 = match self {
+            메시지::새_블록(block) => block.인코딩(),
+            메시지::새_트랜잭션(트랜잭션) => 트랜잭션.인코딩(),
+        };
+        let mut 출력 = 프로토콜_버전.to_be_bytes().to_vec();
+        출력.push(self.id());
+        codec::길이_접두_바이트_쓰기(&mut 출력, &본문);
+        출력
+    }
+
+    // 봉투를 열어 보낸 쪽의 프로토콜 버전과 메시지를 돌려줍니다.
+    pub fn 봉투_해석(바이트: &[u8]) -> Result<(u16, 메시지), 프로토콜_오류> {
+        let mut 입력 = 읽기_커서::new(바이트);
+        let 버전 = 입력.u16()?;
+        if 버전 < 최소_호환_버전 {
+            return Err(프로토콜_오류::호환되지_않는_버전(버전));
+        }
+        let id = 입력.u8()?;
+        let mut 본문 = 읽기_커서::new(입력.길이_접두_바이트()?);
+        if 입력.남은_길이() != 0 {
+            return Err(디코딩_오류::남은_바이트(입력.남은_길이()).into());
+        }
+
+        let 메시지 = match id {
+            새_블록_ID => 메시지::새_블록(블록::인코딩_읽기(&mut 본문)?),
+            새_트랜잭션_ID => 메시지::새_트랜잭션(트랜잭션::인코딩_읽기(&mut 본문)?),
+            _ => return Err(프로토콜_오류::알_수_없는_메시지 { 버전, id }),
+        };
+        if 버전 <= 프로토콜_버전 && 본문.남은_길이() != 0 {
+            return Err(디코딩_오류::남은_바이트(본문.남은_길이()).into());
+        }
+        Ok((버전, 메시지))
+    }
+}
+
+// 헤더와 블록은 gossipsub 로 모두에게 퍼뜨리지 않고 요청한 피어와 1:1 로 주고받습니다.
+// 버전은 프로토콜 이름에 담기므로 본문 배치를 바꾸면 이름의 숫자를 올리고 옛 이름도 함께 지원합니다.
+// 이름에 체인 ID 가 들어가므로 다른 체인의 노드와는 프로토콜 협상부터 실패합니다.
+const 동기화_프로토콜_버전: &str = "sync/1";
+// 한 요청이나 응답의 최대 크기입니다. 이보다 크면 읽지 않고 실패로 처리합니다.
+const 최대_동기화_메시지_크기: usize = 8 * 1024 * 1024;
+
+const 헤더들_ID: u8 = 1;
+const 블록들_ID: u8 = 2;
+const 인사_ID: u8 = 3;
+const 팁_ID: u8 = 4;
+
+#[derive(Debug, Clone)]
+pub enum 동기화_요청 {
+    // 로케이터 중 처음으로 아는 해시 다음부터의 헤더들
+    헤더들 { 로케이터: Vec<String> },
+    블록들 { 해시들: Vec<String> },
+    // 피어를 찾으면 먼저 보내 같은 체인인지 확인합니다. 응답도 같은 내용입니다.
+    인사(인사_정보),
+    // 체인 끝의 높이, 해시와 누적 작업량. 물은 피어에게만 답하므로 피어 수만큼의 요청으로 끝납니다.
+    팁,
+}
+
+#[derive(Debug, Clone)]
+pub enum 동기화_응답 {
+    헤더들(Vec<블록_헤더>),
+    블록들(Vec<블록>),
+    인사(인사_정보),
+    팁(팁_정보),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct 인사_정보 {
+    pub 체인_id: String,
+    pub 제네시스_해시: String,
+    pub 프로토콜_버전: u16,
+}
+
+impl 인사_정보 {
+    pub fn 우리_노드() -> Self {
+        인사_정보 {
+            체인_id: genesis::체인_id().to_string(),
+            제네시스_해시: genesis::제네시스_해시().to_string(),
+            프로토콜_버전,
+        }
+    }
+
+    // 함께 지낼 수 없는 피어면 그 이유를 돌려줍니다.
+    pub fn 호환_확인(&self) -> Result<(), String> {
+        if self.체인_id != genesis::체인_id() {
+            return Err(format!("체인 ID {} 가 우리 체인 {} 와 다릅니다", self.체인_id, genesis::체인_id()));
+        }
+        if self.제네시스_해시 != genesis::제네시스_해시() {
+            return Err(format!(
+                "제네시스 {} 가 우리 제네시스 {} 와 다릅니다",
+                self.제네시스_해시,
+                genesis::제네시스_해시()
+            ));
+        }
+        if self.프로토콜_버전 < 최소_호환_버전 {
+            return Err(프로토콜_오류::호환되지_않는_버전(self.프로토콜_버전).to_string());
+        }
+        Ok(())
+    }
+}
+
+impl 이진_인코딩 for 인사_정보 {
+    fn 인코딩_쓰기(&self, 출력: &mut Vec<u8>) {
+        codec::문자열_쓰기(출력, &self.체인_id);
+        codec::문자열_쓰기(출력, &self.제네시스_해시);
+        출력.extend_from_slice(&self.프로토콜_버전.to_be_bytes());
+    }
+
+    fn 인코딩_읽기(입력: &mut 읽기_커서) -> Result<Self, 디코딩_오류> {
+        Ok(인사_정보 {
+            체인_id: 입력.문자열()?,
+            제네시스_해시: 입력.문자열()?,
+            프로토콜_버전: 입력.u16()?,
+        })
+    }
+}
+
+impl 이진_인코딩 for 동기화_요청 {
+    fn 인코딩_쓰기(&self, 출력: &mut Vec<u8>) {
+        match self {
+            동기화_요청::헤더들 { 로케이터 } => {
+                출력.push(헤더들_ID);
+                codec::목록_쓰기(출력, 로케이터);
+            }
+            동기화_요청::블록들 { 해시들 } => {
+                출력.push(블록들_ID);
+                codec::목록_쓰기(출력, 해시들);
+            }
+            동기화_요청::인사(인사) => {
+                출력.push(인사_ID);
+                인사.인코딩_쓰기(출력);
+            }
+            동기화_요청::팁 => 출력.push(팁_ID),
+        }
+    }
+
+    fn 인코딩_읽기(입력: &mut 읽기_커서) -> Result<Self, 디코딩_오류> {
+        match 입력.u8()? {
+            헤더들_ID => Ok(동기화_요청::헤더들 { 로케이터: 입력.목록()? }),
+            블록들_ID => Ok(동기화_요청::블록들 { 해시들: 입력.목록()? }),
+            인사_ID => Ok(동기화_요청::인사(인사_정보::인코딩_읽기(입력)?)),
+            팁_ID => Ok(동기화_요청::팁),
+            id => Err(디코딩_오류::알_수_없는_종류(id)),
+        }
+    }
+}
+
+impl 이진_인코딩 for 동기화_응답 {
+    fn 인코딩_쓰기(&self, 출력: &mut Vec<u8>) {
+        match self {
+            동기화_응답::헤더들(헤더들) => {
+                출력.push(헤더들_ID);
+                codec::목록_쓰기(출력, 헤더들);
+            }
+            동기화_응답::블록들(블록들) => {
+                출력.push(블록들_ID);
+                codec::목록_쓰기(출력, 블록들);
+            }
+            동기화_응답::인사(인사) => {
+                출력.push(인사_ID);
+                인사.인코딩_쓰기(출력);
+            }
+            동기화_응답::팁(팁) => {
+                출력.push(팁_ID);
+                팁.인코딩_쓰기(출력);
+            }
+        }
+    }
+
+    fn 인코딩_읽기(입력: &mut 읽기_커서) -> Result<Self, 디코딩_오류> {
+        match 입력.u8()? {
+            헤더들_ID => Ok(동기화_응답::헤더들(입력.목록()?)),
+            블록들_ID => Ok(동기화_응답::블록들(입력.목록()?)),
+            인사_ID => Ok(동기화_응답::인사(인사_정보::인코딩_읽기(입력)?)),
+            팁_ID => Ok(동기화_응답::팁(팁_정보::인코딩_읽기(입력)?)),
+            id => Err(디코딩_오류::알_수_없는_종류(id)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct 동기화_프로토콜(String);
+
+impl 동기화_프로토콜 {
+    // 이 노드의 체인 ID 가 들어간 프로토콜 이름입니다.
+    pub fn 현재() -> Self {
+        동기화_프로토콜(genesis::이름공간(동기화_프로토콜_버전))
+    }
+}
+
+impl ProtocolName for 동기화_프로토콜 {
+    fn protocol_name(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+// 요청과 응답 하나를 길이 접두사가 붙은 이진 인코딩으로 주고받습니다.
+#[derive(Clone, Default)]
+pub struct 동기화_코덱;
+
+fn 읽기_오류(e: 디코딩_오류) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+#[async_trait]
+impl RequestResponseCodec for 동기화_코덱 {
+    type Protocol = 동기화_프로토콜;
+    type Request = 동기화_요청;
+    type Response = 동기화_응답;
+
+    async fn read_request<T>(&mut self, _: &동기화_프로토콜, io: &mut T) -> io::Result<동기화_요청>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let 바이트 = read_length_prefixed(io, 최대_동기화_메시지_크기).await?;
+        동기화_요청::디코딩(&바이트).map_err(읽기_오류)
+    }
+
+    async fn read_response<T>(&mut self, _: &동기화_프로토콜, io: &mut T) -> io::Result<동기화_응답>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let 바이트 = read_length_prefixed(io, 최대_동기화_메시지_크기).await?;
+        동기화_응답::디코딩(&바이트).map_err(읽기_오류)
+    }
+
+    async fn write_request<T>(&mut self, _: &동기화_프로토콜, io: &mut T, 요청: 동기화_요청) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_length_prefixed(io, 요청.인코딩()).await?;
+        io.close().await
+    }
+
+    async fn write_response<T>(&mut self, _: &동기화_프로토콜, io: &mut T, 응답: 동기화_응답) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_length_prefixed(io, 응답.인코딩()).await?;
+        io.close().await
+    }
+}
@@ -0,0 +1,64 @@
+use super::블록;
+use crate::transaction::트랜잭션;
+use std::collections::HashSet;
+
+// 주 체인이 바뀔 때마다 구독자에게 보내는 알림입니다. 재구성이면 해제됨이 팁 쪽부터 먼저 오고,
+// 이어서 연결됨이 분기점 쪽부터 옵니다. 받은 순서대로 반영하면 주 체인과 같은 모습이 됩니다.
+#[derive(Debug, Clone)]
+pub enum 체인_이벤트 {
+    연결됨(블록),
+    해제됨(블록),
+    // 재구성 한 번의 해제됨·연결됨 이벤트들 뒤에 오는 요약입니다. 해시들은 분기점 쪽부터 담습니다.
+    // 블록을 하나씩 반영하는 구독자는 무시해도 됩니다.
+    재구성됨 { 해제된: Vec<String>, 연결된: Vec<String> },
+}
+
+// 두 체인이 처음부터 같은 블록을 가진 길이, 즉 공통 조상까지의 블록 수입니다.
+pub fn 공통_길이(기존: &[블록], 새: &[블록]) -> usize {
+    기존.iter()
+        .zip(새.iter())
+        .take_while(|(기존, 새것)| 기존.해시 == 새것.해시)
+        .count()
+}
+
+// 체인 교체 한 번에서 떼어 낸 블록들과 새로 붙인 블록들입니다. 둘 다 분기점 쪽부터 담습니다.
+pub struct 재구성 {
+    pub 해제된: Vec<블록>,
+    pub 연결된: Vec<블록>,
+}
+
+impl 재구성 {
+    pub fn 이벤트들(&self) -> Vec<체인_이벤트> {
+        let mut 이벤트들: Vec<체인_이벤트> = self
+            .해제된
+            .iter()
+            .rev()
+            .cloned()
+            .map(체인_이벤트::해제됨)
+            .chain(self.연결된.iter().cloned().map(체인_이벤트::연결됨))
+            .collect();
+        if !self.해제된.is_empty() {
+            이벤트들.push(체인_이벤트::재구성됨 {
+                해제된: self.해제된.iter().map(|b| b.해시.clone()).collect(),
+                연결된: self.연결된.iter().map(|b| b.해시.clone()).collect(),
+            });
+        }
+        이벤트들
+    }
+
+    // 떼어 낸 블록에만 있던 트랜잭션들입니다. 거래 풀로 돌려보내 새 체인에서 다시 실릴 수 있게 합니다.
+    // 블록 순서대로 담으므로 한 주소의 트랜잭션은 논스 순서를 지킵니다.
+    pub fn 되돌릴_트랜잭션들(&self) -> Vec<트랜잭션> {
+        let 새_체인에_있음: HashSet<String> = self
+            .연결된
+            .iter()
+            .flat_map(|b| b.트랜잭션들.iter().map(|t| t.해시()))
+            .collect();
+        self.해제된
+            .iter()
+            .flat_map(|b| b.트랜잭션들.iter())
+            .filter(|t| !새_체인에_있음.contains(&t.해시()))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,476 @@
+use super::{블록, 체인_작업량};
+use crate::{
+    genesis,
+    peer2peer::앱동작_구조체,
+    transaction::트랜잭션,
+};
+use hyper::{
+    body::HttpBody,
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use libp2p::swarm::Swarm;
+use log::{error, info};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{convert::Infallible, future::Future, net::SocketAddr};
+use tokio::{
+    spawn,
+    sync::{mpsc, oneshot},
+};
+
+// JSON-RPC 2.0 을 HTTP POST 로 받습니다. 요청 하나나 배치(배열)를 보낼 수 있습니다.
+//
+//   curl -s http://127.0.0.1:8545 -d '{"jsonrpc":"2.0","id":1,"method":"getTip"}'
+//
+// 메서드 (인자는 위치 배열이나 이름 객체로 줍니다)
+//   getBlockByHeight [height]       주 체인의 그 높이 블록, 없으면 null
+//   getBlockByHash [hash]           주 체인의 그 해시 블록, 없으면 null
+//   getTip                          팁의 높이, 해시, 타임스탬프, 난이도 비트와 체인 ID
+//   getPeers                        알려진 피어들과 연결 여부, gossipsub 점수
+//   getBlocks [page, limit]         높은 블록부터 page 번째 쪽 (page 는 1 부터, limit 는 최대 100)
+//   searchBlocks [query, page, limit] 데이터에 검색어가 모두 들어 있는 블록들 (대소문자 무시)
+//   getChainStats [window]          높이, 누적 작업량, 최근 window 블록의 평균 블록 시간, 난이도 변화
+//   submitData [data]               데이터를 담은 블록을 채굴하기 시작하고 그 높이를 돌려줌
+//   submitTransaction [transaction] 서명된 트랜잭션을 거래 풀에 넣고 퍼뜨린 뒤 해시를 돌려줌
+//
+// HTTP 처리는 별도 작업에서 하고, 메서드는 메인 루프가 swarm 의 앱 상태로 실행합니다.
+
+// 이보다 큰 요청 본문은 읽지 않습니다.
+const 최대_요청_크기: u64 = 1024 * 1024;
+const 기본_쪽_크기: usize = 20;
+const 최대_쪽_크기: usize = 100;
+const 기본_통계_구간: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct 호출_요청 {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    // 없으면 알림이라 응답하지 않습니다.
+    #[serde(default)]
+    id: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct 호출_오류 {
+    #[serde(rename = "code")]
+    pub 코드: i64,
+    #[serde(rename = "message")]
+    pub 메시지: String,
+}
+
+impl 호출_오류 {
+    fn 해석_실패() -> Self {
+        호출_오류 { 코드: -32700, 메시지: "JSON 을 해석할 수 없습니다".to_string() }
+    }
+
+    fn 잘못된_요청() -> Self {
+        호출_오류 { 코드: -32600, 메시지: "JSON-RPC 2.0 요청이 아닙니다".to_string() }
+    }
+
+    fn 없는_메서드(메서드: &str) -> Self {
+        호출_오류 { 코드: -32601, 메시지: format!("메서드 {} 가 없습니다", 메서드) }
+    }
+
+    fn 잘못된_인자(사유: String) -> Self {
+        호출_오류 { 코드: -32602, 메시지: 사유 }
+    }
+
+    fn 내부_오류(사유: &str) -> Self {
+        호출_오류 { 코드: -32603, 메시지: 사유.to_string() }
+    }
+
+    // 요청은 올바르지만 노드가 받아들이지 않았습니다. 거래 풀이 거부한 트랜잭션 등입니다.
+    fn 거부됨(사유: String) -> Self {
+        호출_오류 { 코드: -32000, 메시지: 사유 }
+    }
+}
+
+type 호출_결과 = Result<Value, 호출_오류>;
+
+// HTTP 작업이 메인 루프로 넘기는 호출입니다. 메인 루프가 실행한 결과를 응답으로 돌려보냅니다.
+pub struct 대기_호출 {
+    메서드: String,
+    인자: Value,
+    응답: oneshot::Sender<호출_결과>,
+}
+
+pub fn 서버_시작(주소: SocketAddr, 송신자: mpsc::UnboundedSender<대기_호출>) -> Result<(), hyper::Error> {
+    http_서버_시작(주소, "JSON-RPC", 송신자, http_처리)
+}
+
+// JSON-RPC 와 블록 탐색기 API 가 같이 씁니다. 주소에 바로 bind 하고, 요청 처리는 별도 작업에서
+// 합니다. bind 에 실패하면 오류를 돌려줍니다.
+pub fn http_서버_시작<F, R>(
+    주소: SocketAddr,
+    이름: &'static str,
+    송신자: mpsc::UnboundedSender<대기_호출>,
+    처리: F,
+) -> Result<(), hyper::Error>
+where
+    F: Fn(Request<Body>, mpsc::UnboundedSender<대기_호출>) -> R + Copy + Send + 'static,
+    R: Future<Output = Result<Response<Body>, Infallible>> + Send + 'static,
+{
+    let 빌더 = Server::try_bind(&주소)?;
+    let 서비스 = make_service_fn(move |_| {
+        let 송신자 = 송신자.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |요청| 처리(요청, 송신자.clone()))) }
+    });
+    let 서버 = 빌더.serve(서비스);
+    info!("{} 를 http://{} 에서 받습니다", 이름, 서버.local_addr());
+    spawn(async move {
+        if let Err(e) = 서버.await {
+            error!("{} 서버가 멈췄습니다: {}", 이름, e);
+        }
+    });
+    Ok(())
+}
+
+fn http_응답(상태: StatusCode, 본문: Body) -> Response<Body> {
+    let mut 응답 = Response::new(본문);
+    *응답.status_mut() = 상태;
+    응답
+}
+
+enum 본문_오류 {
+    너무_큼,
+    읽기(hyper::Error),
+}
+
+// Content-Length 가 없거나 거짓인 요청도 있으므로 조각을 받을 때마다 크기를 더해 보고,
+// 최대_요청_크기를 넘는 순간 나머지는 읽지 않고 멈춥니다.
+async fn 본문_읽기(mut 본문: Body) -> Result<Vec<u8>, 본문_오류> {
+    let mut 읽은_본문 = Vec::new();
+    while let Some(조각) = 본문.data().await {
+        let 조각 = 조각.map_err(본문_오류::읽기)?;
+        if (읽은_본문.len() + 조각.len()) as u64 > 최대_요청_크기 {
+            return Err(본문_오류::너무_큼);
+        }
+        읽은_본문.extend_from_slice(&조각);
+    }
+    Ok(읽은_본문)
+}
+
+async fn http_처리(
+    요청: Request<Body>,
+    송신자: mpsc::UnboundedSender<대기_호출>,
+) -> Result<Response<Body>, Infallible> {
+    if 요청.method() != Method::POST {
+        let mut 응답 = http_응답(StatusCode::METHOD_NOT_ALLOWED, Body::empty());
+        응답.headers_mut()
+            .insert(header::ALLOW, header::HeaderValue::from_static("POST"));
+        return Ok(응답);
+    }
+    if 요청.body().size_hint().upper().map_or(false, |크기| 크기 > 최대_요청_크기) {
+        return Ok(http_응답(StatusCode::PAYLOAD_TOO_LARGE, Body::empty()));
+    }
+    let 본문 = match 본문_읽기(요청.into_body()).await {
+        Ok(본문) => 본문,
+        Err(본문_오류::너무_큼) => return Ok(http_응답(StatusCode::PAYLOAD_TOO_LARGE, Body::empty())),
+        Err(본문_오류::읽기(e)) => {
+            info!("JSON-RPC 요청 본문을 읽을 수 없습니다: {}", e);
+            return Ok(http_응답(StatusCode::BAD_REQUEST, Body::empty()));
+        }
+    };
+
+    let 결과 = match serde_json::from_slice::<Value>(&본문) {
+        Err(_) => Some(응답_만들기(Value::Null, Err(호출_오류::해석_실패()))),
+        Ok(Value::Array(요청들)) if 요청들.is_empty() => {
+            Some(응답_만들기(Value::Null, Err(호출_오류::잘못된_요청())))
+        }
+        Ok(Value::Array(요청들)) => {
+            let mut 응답들 = vec![];
+            for 요청 in 요청들 {
+                응답들.extend(호출_처리(요청, &송신자).await);
+            }
+            if 응답들.is_empty() {
+                None
+            } else {
+                Some(Value::Array(응답들))
+            }
+        }
+        Ok(요청) => 호출_처리(요청, &송신자).await,
+    };
+
+    // 알림만 있었으면 돌려줄 응답이 없습니다.
+    Ok(match 결과 {
+        Some(값) => {
+            let mut 응답 = http_응답(StatusCode::OK, Body::from(값.to_string()));
+            응답.headers_mut().insert(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            );
+            응답
+        }
+        None => http_응답(StatusCode::NO_CONTENT, Body::empty()),
+    })
+}
+
+fn 응답_만들기(id: Value, 결과: 호출_결과) -> Value {
+    match 결과 {
+        Ok(값) => json!({ "jsonrpc": "2.0", "result": 값, "id": id }),
+        Err(오류) => json!({ "jsonrpc": "2.0", "error": 오류, "id": id }),
+    }
+}
+
+// 요청 하나를 메인 루프에 넘기고 결과를 기다립니다. 알림이면 None 을 돌려줍니다.
+async fn 호출_처리(값: Value, 송신자: &mpsc::UnboundedSender<대기_호출>) -> Option<Value> {
+    let 요청: 호출_요청 = match serde_json::from_value(값) {
+        Ok(요청) => 요청,
+        Err(_) => return Some(응답_만들기(Value::Null, Err(호출_오류::잘못된_요청()))),
+    };
+    if 요청.jsonrpc != "2.0" {
+        return Some(응답_만들기(요청.id.unwrap_or(Value::Null), Err(호출_오류::잘못된_요청())));
+    }
+
+    let 결과 = 호출(송신자, 요청.method, 요청.params).await;
+    요청.id.map(|id| 응답_만들기(id, 결과))
+}
+
+// 메서드를 메인 루프에 넘겨 실행하고 결과를 기다립니다. 탐색기 API 도 이 함수로 조회합니다.
+pub async fn 호출(송신자: &mpsc::UnboundedSender<대기_호출>, 메서드: String, 인자: Value) -> 호출_결과 {
+    let (응답_송신, 응답_수신) = oneshot::channel();
+    let 대기 = 대기_호출 {
+        메서드,
+        인자,
+        응답: 응답_송신,
+    };
+    if 송신자.send(대기).is_err() {
+        return Err(호출_오류::내부_오류("노드가 종료되는 중입니다"));
+    }
+    응답_수신
+        .await
+        .unwrap_or_else(|_| Err(호출_오류::내부_오류("노드가 응답하지 않았습니다")))
+}
+
+// 메인 루프에서 호출합니다. HTTP 쪽이 먼저 끊겼으면 결과를 받을 곳이 없으므로 버립니다.
+pub fn 호출_처리_함수(호출: 대기_호출, swarm: &mut Swarm<앱동작_구조체>) {
+    let 결과 = 메서드_실행(&호출.메서드, &호출.인자, swarm);
+    let _ = 호출.응답.send(결과);
+}
+
+// 인자는 위치 배열([값, ...])이나 이름 객체({"이름": 값})로 받습니다. 없거나 null 이면 None 입니다.
+fn 선택_인자<T: DeserializeOwned>(인자: &Value, 위치: usize, 이름: &str) -> Result<Option<T>, 호출_오류> {
+    let 값 = match 인자 {
+        Value::Array(목록) => 목록.get(위치),
+        Value::Object(항목들) => 항목들.get(이름),
+        _ => None,
+    };
+    match 값 {
+        None | Some(Value::Null) => Ok(None),
+        Some(값) => serde_json::from_value(값.clone())
+            .map(Some)
+            .map_err(|e| 호출_오류::잘못된_인자(format!("인자 {} 가 잘못되었습니다: {}", 이름, e))),
+    }
+}
+
+fn 인자_읽기<T: DeserializeOwned>(인자: &Value, 위치: usize, 이름: &str) -> Result<T, 호출_오류> {
+    선택_인자(인자, 위치, 이름)?.ok_or_else(|| 호출_오류::잘못된_인자(format!("인자 {} 가 필요합니다", 이름)))
+}
+
+// page 는 1 부터 셉니다. limit 는 최대_쪽_크기 를 넘지 못합니다.
+fn 쪽_인자(인자: &Value, 위치: usize) -> Result<(usize, usize), 호출_오류> {
+    let 쪽: usize = 선택_인자(인자, 위치, "page")?.unwrap_or(1);
+    let 크기: usize = 선택_인자(인자, 위치 + 1, "limit")?.unwrap_or(기본_쪽_크기);
+    if 쪽 == 0 || 크기 == 0 || 크기 > 최대_쪽_크기 {
+        return Err(호출_오류::잘못된_인자(format!(
+            "page 는 1 이상, limit 는 1 에서 {} 사이여야 합니다",
+            최대_쪽_크기
+        )));
+    }
+    Ok((쪽, 크기))
+}
+
+// 높은 블록부터 나열한 목록에서 한 쪽을 잘라 전체 개수와 함께 돌려줍니다.
+fn 쪽_값(높은_순: Vec<&블록>, 쪽: usize, 크기: usize) -> Value {
+    let 전체 = 높은_순.len();
+    let 블록들: Vec<&블록> = 높은_순.into_iter().skip((쪽 - 1).saturating_mul(크기)).take(크기).collect();
+    json!({ "total": 전체, "page": 쪽, "limit": 크기, "blocks": 블록들 })
+}
+
+// 최근 구간 블록의 평균 블록 시간(초)과, 난이도 비트가 바뀐 높이들입니다.
+// 구간이 체인보다 길면 제네시스부터 셉니다. 빈 체인이면 높이와 팁이 null 인 통계를 돌려줍니다.
+fn 체인_통계(블록들: &[블록], 구간: usize) -> Value {
+    let 팁 = match 블록들.last() {
+        Some(팁) => 팁,
+        None => {
+            return json!({
+                "height": null,
+                "tipHash": null,
+                "totalWork": "0",
+                "averageBlockTime": null,
+                "averageBlockTimeWindow": 0,
+                "difficultyHistory": [],
+            })
+        }
+    };
+    let 시작 = &블록들[블록들.len().saturating_sub(구간.saturating_add(1))];
+    let 평균_블록_시간 = if 팁.id > 시작.id {
+        Some((팁.타임스탬프 - 시작.타임스탬프) as f64 / (팁.id - 시작.id) as f64)
+    } else {
+        None
+    };
+    let 난이도_변화: Vec<Value> = 블록들
+        .iter()
+        .enumerate()
+        .filter(|(위치, block)| *위치 == 0 || 블록들[위치 - 1].난이도_비트 != block.난이도_비트)
+        .map(|(_, block)| json!({ "height": block.id, "difficultyBits": block.난이도_비트 }))
+        .collect();
+    json!({
+        "height": 팁.id,
+        "tipHash": 팁.해시,
+        // u128 은 JSON 숫자로 정확히 담을 수 없어 문자열로 보냅니다.
+        "totalWork": 체인_작업량(블록들).to_string(),
+        "averageBlockTime": 평균_블록_시간,
+        "averageBlockTimeWindow": 팁.id - 시작.id,
+        "difficultyHistory": 난이도_변화,
+    })
+}
+
+fn 블록_값(block: Option<&블록>) -> 호출_결과 {
+    serde_json::to_value(block).map_err(|_| 호출_오류::내부_오류("블록을 JSON 으로 바꿀 수 없습니다"))
+}
+
+fn 메서드_실행(메서드: &str, 인자: &Value, swarm: &mut Swarm<앱동작_구조체>) -> 호출_결과 {
+    match 메서드 {
+        "getBlockByHeight" => {
+            let 높이: u64 = 인자_읽기(인자, 0, "height")?;
+            블록_값(swarm.behaviour().app.블록들.iter().find(|b| b.id == 높이))
+        }
+        "getBlockByHash" => {
+            let 해시: String = 인자_읽기(인자, 0, "hash")?;
+            블록_값(swarm.behaviour().app.블록들.iter().find(|b| b.해시 == 해시))
+        }
+        "getTip" => Ok(match swarm.behaviour().app.블록들.last() {
+            Some(팁) => json!({
+                "height": 팁.id,
+                "hash": 팁.해시,
+                "timestamp": 팁.타임스탬프,
+                "difficultyBits": 팁.난이도_비트,
+                "chainId": genesis::체인_id(),
+            }),
+            None => Value::Null,
+        }),
+        "getPeers" => {
+            let 피어들 = swarm.behaviour_mut().알려진_피어들();
+            let 목록: Vec<Value> = 피어들
+                .iter()
+                .map(|피어| {
+                    json!({
+                        "peerId": 피어.to_string(),
+                        "connected": swarm.is_connected(피어),
+                        "score": swarm.behaviour().gossipsub.peer_score(피어),
+                    })
+                })
+                .collect();
+            Ok(Value::Array(목록))
+        }
+        "getBlocks" => {
+            let (쪽, 크기) = 쪽_인자(인자, 0)?;
+            Ok(쪽_값(swarm.behaviour().app.블록들.iter().rev().collect(), 쪽, 크기))
+        }
+        "searchBlocks" => {
+            let 검색어: String = 인자_읽기(인자, 0, "query")?;
+            let (쪽, 크기) = 쪽_인자(인자, 1)?;
+            let 낱말들: Vec<String> = 검색어.split_whitespace().map(str::to_lowercase).collect();
+            if 낱말들.is_empty() {
+                return Err(호출_오류::잘못된_인자("검색어가 비어 있습니다".to_string()));
+            }
+            let 찾은_블록들 = swarm
+                .behaviour()
+                .app
+                .블록들
+                .iter()
+                .rev()
+                .filter(|block| {
+                    let 데이터 = block.데이터.to_lowercase();
+                    낱말들.iter().all(|낱말| 데이터.contains(낱말.as_str()))
+                })
+                .collect();
+            Ok(쪽_값(찾은_블록들, 쪽, 크기))
+        }
+        "getChainStats" => {
+            let 구간: usize = 선택_인자(인자, 0, "window")?.unwrap_or(기본_통계_구간);
+            if 구간 == 0 {
+                return Err(호출_오류::잘못된_인자("window 는 1 이상이어야 합니다".to_string()));
+            }
+            Ok(체인_통계(&swarm.behaviour().app.블록들, 구간))
+        }
+        "submitData" => {
+            let 데이터: String = 인자_읽기(인자, 0, "data")?;
+            let behaviour = swarm.behaviour_mut();
+            if behaviour.app.블록들.is_empty() {
+                return Err(호출_오류::거부됨("아직 제네시스 블록이 없습니다".to_string()));
+            }
+            let 높이 = behaviour.채굴_시작(데이터);
+            Ok(json!({ "height": 높이 }))
+        }
+        "submitTransaction" => {
+            let 트랜잭션: 트랜잭션 = 인자_읽기(인자, 0, "transaction")?;
+            let 해시 = 트랜잭션.해시();
+            swarm
+                .behaviour_mut()
+                .트랜잭션_제출(트랜잭션)
+                .map_err(|사유| 호출_오류::거부됨(사유.to_string()))?;
+            Ok(json!({ "hash": 해시 }))
+        }
+        _ => Err(호출_오류::없는_메서드(메서드)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::body::Bytes;
+
+    #[tokio::test]
+    async fn 끝없는_본문도_한도를_넘으면_멈춤() {
+        let (mut 송신, 본문) = Body::channel();
+        // Content-Length 없이 계속 보내도 한도를 넘는 순간 읽기를 멈춰야 합니다.
+        spawn(async move { while 송신.send_data(Bytes::from(vec![0u8; 64 * 1024])).await.is_ok() {} });
+        assert!(matches!(본문_읽기(본문).await, Err(본문_오류::너무_큼)));
+    }
+
+    #[tokio::test]
+    async fn 한도_안의_본문은_모두_읽음() {
+        let 본문 = Body::from(vec![1u8; 최대_요청_크기 as usize]);
+        assert_eq!(본문_읽기(본문).await.ok().map(|b| b.len()), Some(최대_요청_크기 as usize));
+    }
+
+    fn 예시_체인(길이: u64) -> Vec<블록> {
+        (0..길이)
+            .map(|id| 블록 {
+                id,
+                타임스탬프: id as i64 * 10,
+                이전_해시: String::new(),
+                데이터: String::new(),
+                트랜잭션들: vec![],
+                머클_루트: String::new(),
+                채굴자: String::new(),
+                논스: 0,
+                해시: format!("{:064x}", id),
+                난이도_비트: 0x207fffff,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn 통계_구간이_체인보다_길면_제네시스부터() {
+        let 통계 = 체인_통계(&예시_체인(3), usize::MAX);
+        assert_eq!(통계["height"], 2);
+        assert_eq!(통계["averageBlockTimeWindow"], 2);
+        assert_eq!(통계["averageBlockTime"], 10.0);
+        assert_eq!(통계["difficultyHistory"].as_array().map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn 빈_체인의_통계() {
+        let 통계 = 체인_통계(&[], 기본_통계_구간);
+        assert!(통계.is_object());
+        assert!(통계["height"].is_null());
+        assert_eq!(통계["averageBlockTimeWindow"], 0);
+    }
+}
@@ -0,0 +1,230 @@
+use super::블록;
+use std::{collections::HashMap, fmt};
+
+// 블록을 채굴한 주소가 받는 보상입니다. 블록에 실린 트랜잭션 수수료도 함께 받습니다.
+pub const 블록_보상: u64 = 50;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct 계정 {
+    pub 잔액: u64,
+    // 이 주소가 다음에 보낼 트랜잭션이 가져야 하는 논스
+    pub 논스: u64,
+}
+
+// 체인의 모든 블록을 순서대로 적용해 얻은 계정들의 상태입니다.
+#[derive(Debug, Clone, Default)]
+pub struct 상태 {
+    계정들: HashMap<String, 계정>,
+}
+
+// 블록 하나를 적용하기 직전 값입니다. 되돌릴 때 그대로 덮어씁니다.
+// None 은 적용 전에는 계정이 없었다는 뜻입니다.
+#[derive(Debug, Clone, Default)]
+pub struct 되돌리기_기록 {
+    이전_계정들: Vec<(String, Option<계정>)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum 상태_오류 {
+    잔액_부족 { 주소: String, 필요: u64, 잔액: u64 },
+    논스_불일치 { 주소: String, 기대: u64, 실제: u64 },
+    금액_넘침 { 주소: String },
+}
+
+impl fmt::Display for 상태_오류 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            상태_오류::잔액_부족 { 주소, 필요, 잔액 } => {
+                write!(f, "{} 의 잔액 {} 이 필요한 {} 보다 적습니다", 주소, 잔액, 필요)
+            }
+            상태_오류::논스_불일치 { 주소, 기대, 실제 } => {
+                write!(f, "{} 의 논스는 {} 이어야 하는데 {} 입니다 (재사용 또는 건너뜀)", 주소, 기대, 실제)
+            }
+            상태_오류::금액_넘침 { 주소 } => write!(f, "{} 의 잔액이 u64 범위를 넘습니다", 주소),
+        }
+    }
+}
+
+impl 상태 {
+    // 제네시스 명세의 할당으로 시작 상태를 만듭니다. 같은 주소가 여러 번 나오면 금액을 더합니다.
+    pub fn 초기_할당<'a>(할당들: impl IntoIterator<Item = (&'a str, u64)>) -> Result<Self, 상태_오류> {
+        let mut 새_상태 = 상태::default();
+        for (주소, 금액) in 할당들 {
+            let 계정 = 새_상태.계정들.entry(주소.to_string()).or_default();
+            계정.잔액 = 계정
+                .잔액
+                .checked_add(금액)
+                .ok_or_else(|| 상태_오류::금액_넘침 { 주소: 주소.to_string() })?;
+        }
+        Ok(새_상태)
+    }
+
+    pub fn 계정(&self, 주소: &str) -> 계정 {
+        self.계정들.get(주소).cloned().unwrap_or_default()
+    }
+
+    // 블록의 트랜잭션들과 채굴 보상을 적용합니다. 하나라도 실패하면 이 블록에서 바꾼
+    // 내용을 모두 되돌린 뒤 오류를 돌려주므로, 상태는 적용 전과 같게 남습니다.
+    pub fn 블록_적용(&mut self, block: &블록) -> Result<되돌리기_기록, 상태_오류> {
+        let mut 기록 = 되돌리기_기록::default();
+        match self.블록_적용_내부(block, &mut 기록) {
+            Ok(()) => Ok(기록),
+            Err(e) => {
+                self.되돌리기(기록);
+                Err(e)
+            }
+        }
+    }
+
+    fn 블록_적용_내부(&mut self, block: &블록, 기록: &mut 되돌리기_기록) -> Result<(), 상태_오류> {
+        let mut 수수료_합계: u64 = 0;
+        for 트랜잭션 in &block.트랜잭션들 {
+            let 송신_계정 = self.계정(&트랜잭션.송신자);
+            if 트랜잭션.논스 != 송신_계정.논스 {
+                return Err(상태_오류::논스_불일치 {
+                    주소: 트랜잭션.송신자.clone(),
+                    기대: 송신_계정.논스,
+                    실제: 트랜잭션.논스,
+                });
+            }
+            let 필요 = 트랜잭션
+                .금액
+                .checked_add(트랜잭션.수수료)
+                .ok_or_else(|| 상태_오류::금액_넘침 { 주소: 트랜잭션.송신자.clone() })?;
+            if 송신_계정.잔액 < 필요 {
+                return Err(상태_오류::잔액_부족 {
+                    주소: 트랜잭션.송신자.clone(),
+                    필요,
+                    잔액: 송신_계정.잔액,
+                });
+            }
+
+            self.기록하며_수정(기록, &트랜잭션.송신자, |계정| {
+                계정.잔액 -= 필요;
+                계정.논스 += 1;
+                Ok(())
+            })?;
+            self.입금(기록, &트랜잭션.수신자, 트랜잭션.금액)?;
+            수수료_합계 = 수수료_합계
+                .checked_add(트랜잭션.수수료)
+                .ok_or_else(|| 상태_오류::금액_넘침 { 주소: block.채굴자.clone() })?;
+        }
+
+        // 제네시스처럼 채굴자가 없는 블록은 보상을 만들지 않습니다.
+        if !block.채굴자.is_empty() {
+            let 보상 = 블록_보상
+                .checked_add(수수료_합계)
+                .ok_or_else(|| 상태_오류::금액_넘침 { 주소: block.채굴자.clone() })?;
+            self.입금(기록, &block.채굴자, 보상)?;
+        }
+        Ok(())
+    }
+
+    fn 입금(&mut self, 기록: &mut 되돌리기_기록, 주소: &str, 금액: u64) -> Result<(), 상태_오류> {
+        self.기록하며_수정(기록, 주소, |계정| {
+            계정.잔액 = 계정
+                .잔액
+                .checked_add(금액)
+                .ok_or_else(|| 상태_오류::금액_넘침 { 주소: 주소.to_string() })?;
+            Ok(())
+        })
+    }
+
+    // 이 블록에서 처음 건드리는 계정이면 바꾸기 전 값을 기록에 남깁니다.
+    fn 기록하며_수정(
+        &mut self,
+        기록: &mut 되돌리기_기록,
+        주소: &str,
+        수정: impl FnOnce(&mut 계정) -> Result<(), 상태_오류>,
+    ) -> Result<(), 상태_오류> {
+        if !기록.이전_계정들.iter().any(|(기록된_주소, _)| 기록된_주소 == 주소) {
+            기록
+                .이전_계정들
+                .push((주소.to_string(), self.계정들.get(주소).cloned()));
+        }
+        수정(self.계정들.entry(주소.to_string()).or_default())
+    }
+
+    pub fn 되돌리기(&mut self, 기록: 되돌리기_기록) {
+        for (주소, 이전_계정) in 기록.이전_계정들.into_iter().rev() {
+            match 이전_계정 {
+                Some(계정) => {
+                    self.계정들.insert(주소, 계정);
+                }
+                None => {
+                    self.계정들.remove(&주소);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::트랜잭션;
+
+    // 상태는 서명을 보지 않으므로 서명 없는 트랜잭션으로 충분합니다.
+    fn 송금(송신자: &str, 수신자: &str, 금액: u64, 논스: u64, 수수료: u64) -> 트랜잭션 {
+        트랜잭션 {
+            송신자: 송신자.to_string(),
+            수신자: 수신자.to_string(),
+            금액,
+            논스,
+            수수료,
+            서명: String::new(),
+        }
+    }
+
+    fn 예시_블록(id: u64, 트랜잭션들: Vec<트랜잭션>) -> 블록 {
+        블록 {
+            id,
+            타임스탬프: 0,
+            데이터: String::new(),
+            트랜잭션들,
+            머클_루트: String::new(),
+            채굴자: "채굴자".to_string(),
+            이전_해시: String::new(),
+            해시: String::new(),
+            논스: 0,
+            난이도_비트: 0x207fffff,
+        }
+    }
+
+    #[test]
+    fn 블록을_되돌리면_적용_전_상태로_돌아감() {
+        let mut 현재 = 상태::초기_할당(vec![("가", 100), ("나", 5)]).unwrap();
+        let 적용_전 = 현재.clone();
+
+        let 첫_블록 = 예시_블록(1, vec![송금("가", "나", 30, 0, 2)]);
+        let 둘째_블록 = 예시_블록(2, vec![송금("나", "다", 10, 0, 1), 송금("가", "다", 5, 1, 0)]);
+        let 첫_기록 = 현재.블록_적용(&첫_블록).unwrap();
+        let 둘째_기록 = 현재.블록_적용(&둘째_블록).unwrap();
+
+        assert_eq!(현재.계정("가"), 계정 { 잔액: 63, 논스: 2 });
+        assert_eq!(현재.계정("나"), 계정 { 잔액: 24, 논스: 1 });
+        assert_eq!(현재.계정("다"), 계정 { 잔액: 15, 논스: 0 });
+        assert_eq!(현재.계정("채굴자").잔액, 2 * 블록_보상 + 3);
+
+        현재.되돌리기(둘째_기록);
+        assert_eq!(현재.계정("나"), 계정 { 잔액: 35, 논스: 0 });
+        assert_eq!(현재.계정("채굴자").잔액, 블록_보상 + 2);
+        현재.되돌리기(첫_기록);
+        // 블록에서 처음 생긴 계정도 사라져야 합니다.
+        assert_eq!(현재.계정들, 적용_전.계정들);
+    }
+
+    #[test]
+    fn 적용에_실패한_블록은_상태를_바꾸지_않음() {
+        let mut 현재 = 상태::초기_할당(vec![("가", 100)]).unwrap();
+        let 적용_전 = 현재.clone();
+
+        // 첫 트랜잭션은 성공하지만 둘째 트랜잭션이 같은 논스를 다시 씁니다.
+        let 블록 = 예시_블록(1, vec![송금("가", "나", 30, 0, 0), 송금("가", "나", 30, 0, 0)]);
+        assert_eq!(
+            현재.블록_적용(&블록).unwrap_err(),
+            상태_오류::논스_불일치 { 주소: "가".to_string(), 기대: 1, 실제: 0 }
+        );
+        assert_eq!(현재.계정들, 적용_전.계정들);
+    }
+}
@@ -0,0 +1,98 @@
+use super::블록;
+use log::warn;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+// 블록을 어디에 보관할지 추상화한 트레이트입니다.
+// 앱은 블록이 추가되거나 체인이 통째로 교체될 때 이 트레이트를 통해 저장소에 기록하고,
+// 노드가 다시 시작되면 전체_블록_읽기로 체인을 되살립니다.
+pub trait BlockStore: Send {
+    fn 블록_추가(&mut self, 블록: &블록) -> io::Result<()>;
+    fn 체인_교체(&mut self, 블록들: &[블록]) -> io::Result<()>;
+    fn 전체_블록_읽기(&self) -> io::Result<Vec<블록>>;
+}
+
+// 지금까지처럼 메모리의 Vec 에만 보관하는 저장소입니다. 재시작하면 내용이 사라집니다.
+#[derive(Default)]
+pub struct 메모리_저장소 {
+    블록들: Vec<블록>,
+}
+
+impl BlockStore for 메모리_저장소 {
+    fn 블록_추가(&mut self, 블록: &블록) -> io::Result<()> {
+        self.블록들.push(블록.clone());
+        Ok(())
+    }
+
+    fn 체인_교체(&mut self, 블록들: &[블록]) -> io::Result<()> {
+        self.블록들 = 블록들.to_vec();
+        Ok(())
+    }
+
+    fn 전체_블록_읽기(&self) -> io::Result<Vec<블록>> {
+        Ok(self.블록들.clone())
+    }
+}
+
+// 블록 하나를 json 한 줄로 파일 끝에 덧붙이는 append-log 저장소입니다.
+// 체인 교체는 임시 파일에 새로 쓴 뒤 rename 하므로 도중에 죽어도 이전 로그가 남습니다.
+pub struct 파일_저장소 {
+    경로: PathBuf,
+    파일: File,
+}
+
+impl 파일_저장소 {
+    pub fn 열기(디렉토리: &Path) -> io::Result<Self> {
+        fs::create_dir_all(디렉토리)?;
+        let 경로 = 디렉토리.join("블록들.log");
+        let 파일 = OpenOptions::new().create(true).append(true).open(&경로)?;
+        Ok(Self { 경로, 파일 })
+    }
+}
+
+impl BlockStore for 파일_저장소 {
+    fn 블록_추가(&mut self, 블록: &블록) -> io::Result<()> {
+        let mut 줄 = serde_json::to_vec(블록)?;
+        줄.push(b'\n');
+        self.파일.write_all(&줄)?;
+        self.파일.sync_data()
+    }
+
+    fn 체인_교체(&mut self, 블록들: &[블록]) -> io::Result<()> {
+        let 임시_경로 = self.경로.with_extension("log.tmp");
+        {
+            let mut 임시_파일 = File::create(&임시_경로)?;
+            for 블록 in 블록들 {
+                let mut 줄 = serde_json::to_vec(블록)?;
+                줄.push(b'\n');
+                임시_파일.write_all(&줄)?;
+            }
+            임시_파일.sync_all()?;
+        }
+        fs::rename(&임시_경로, &self.경로)?;
+        self.파일 = OpenOptions::new().append(true).open(&self.경로)?;
+        Ok(())
+    }
+
+    fn 전체_블록_읽기(&self) -> io::Result<Vec<블록>> {
+        let mut 블록들 = vec![];
+        for (번호, 줄) in BufReader::new(File::open(&self.경로)?).lines().enumerate() {
+            let 줄 = 줄?;
+            if 줄.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<블록>(&줄) {
+                Ok(블록) => 블록들.push(블록),
+                Err(e) => {
+                    // 기록 도중 종료되어 마지막 줄이 잘린 경우입니다. 그 앞까지만 사용합니다.
+                    warn!("{} 의 {}번째 줄을 읽을 수 없어 이후 내용을 무시합니다: {}", self.경로.display(), 번호 + 1, e);
+                    break;
+                }
+            }
+        }
+        Ok(블록들)
+    }
+}
@@ -0,0 +1,409 @@
+use super::{체인_작업량, 앱, 블록, 블록_헤더};
+use crate::{
+    codec::{self, 디코딩_오류, 이진_인코딩, 읽기_커서},
+    protocol::동기화_요청,
+    target::목표값,
+};
+use libp2p::{request_response::RequestId, PeerId};
+use log::{info, warn};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// 헤더 응답 하나에 담는 최대 헤더 수입니다. 이만큼 받으면 이어서 다음 헤더를 요청합니다.
+pub const 헤더_묶음_최대: usize = 500;
+// 블록 요청 하나에 담는 블록 수입니다.
+pub const 본문_묶음_크기: usize = 16;
+// 한 피어에게 동시에 맡기는 블록 요청 수입니다.
+const 피어당_최대_요청: usize = 2;
+// 로케이터에서 팁부터 하나씩 담는 해시 수입니다. 그 뒤로는 간격을 두 배씩 늘립니다.
+const 로케이터_촘촘한_개수: usize = 10;
+
+// 피어가 알려 준 체인 끝의 높이, 해시와 누적 작업량입니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct 팁_정보 {
+    pub 높이: u64,
+    pub 해시: String,
+    pub 작업량: u128,
+}
+
+impl 팁_정보 {
+    pub fn 체인에서(블록들: &[블록]) -> Self {
+        let 마지막 = 블록들.last();
+        팁_정보 {
+            높이: 마지막.map_or(0, |b| b.id),
+            해시: 마지막.map_or_else(String::new, |b| b.해시.clone()),
+            작업량: 체인_작업량(블록들),
+        }
+    }
+}
+
+impl 이진_인코딩 for 팁_정보 {
+    fn 인코딩_쓰기(&self, 출력: &mut Vec<u8>) {
+        출력.extend_from_slice(&self.높이.to_be_bytes());
+        codec::문자열_쓰기(출력, &self.해시);
+        출력.extend_from_slice(&self.작업량.to_be_bytes());
+    }
+
+    fn 인코딩_읽기(입력: &mut 읽기_커서) -> Result<Self, 디코딩_오류> {
+        Ok(팁_정보 {
+            높이: 입력.u64()?,
+            해시: 입력.문자열()?,
+            작업량: 입력.u128()?,
+        })
+    }
+}
+
+// 팁에서부터 처음 몇 개는 하나씩, 그 뒤로는 간격을 두 배씩 늘려 가며 고른 해시들입니다.
+// 마지막은 항상 제네시스라서 상대는 이 중 자신이 아는 가장 앞의 해시로 공통 조상을 찾습니다.
+pub fn 로케이터(블록들: &[블록]) -> Vec<String> {
+    let mut 해시들 = vec![];
+    let mut 위치 = match 블록들.len() {
+        0 => return 해시들,
+        길이 => 길이 - 1,
+    };
+    let mut 간격 = 1;
+    loop {
+        해시들.push(블록들[위치].해시.clone());
+        if 위치 == 0 {
+            return 해시들;
+        }
+        if 해시들.len() >= 로케이터_촘촘한_개수 {
+            간격 *= 2;
+        }
+        위치 = 위치.saturating_sub(간격);
+    }
+}
+
+// 로케이터 중 처음으로 아는 해시 다음부터 헤더들을 돌려줍니다. 아는 해시가 없으면
+// 제네시스부터 보내므로, 받는 쪽은 제네시스가 다른 체인도 알아볼 수 있습니다.
+pub fn 헤더_찾기(블록들: &[블록], 로케이터: &[String]) -> Vec<블록_헤더> {
+    let 시작 = 로케이터
+        .iter()
+        .find_map(|해시| 블록들.iter().position(|b| &b.해시 == 해시))
+        .map_or(0, |위치| 위치 + 1);
+    블록들.iter().skip(시작).take(헤더_묶음_최대).map(블록::헤더).collect()
+}
+
+pub fn 블록_찾기(블록들: &[블록], 해시들: &[String]) -> Vec<블록> {
+    let 찾을_해시들: HashSet<&String> = 해시들.iter().take(본문_묶음_크기).collect();
+    블록들
+        .iter()
+        .filter(|b| 찾을_해시들.contains(&b.해시))
+        .cloned()
+        .collect()
+}
+
+// 요청을 받을 피어와 요청입니다. 동기화는 무엇을 누구에게 물을지만 정하고, 보내는 일은 호출한 쪽이 합니다.
+pub type 보낼_요청 = (PeerId, 동기화_요청);
+
+struct 진행중_요청 {
+    피어: PeerId,
+    해시들: Vec<String>,
+}
+
+// 헤더를 먼저 받아 작업 증명과 연결을 확인한 뒤, 빠진 본문을 여러 피어에게 나누어 받습니다.
+// 본문은 받는 대로 체인 끝에 붙이므로 도중에 끊겨도 이미 붙인 블록은 남고, 다음 동기화는
+// 로케이터로 그 다음부터 이어 받습니다. 받아 두었지만 아직 붙이지 못한 본문은 동기화를 접을 때 함께 버립니다.
+#[derive(Default)]
+pub struct 동기화 {
+    피어_팁들: HashMap<PeerId, 팁_정보>,
+    // 헤더 요청을 보내고 응답을 기다리는 피어
+    헤더_요청_중: Option<PeerId>,
+    // 로칼 체인의 앞 공통_길이 개 블록 뒤에 이어질, 아직 붙이지 않은 (해시, 헤더)들입니다.
+    공통_길이: usize,
+    헤더들: VecDeque<(String, 블록_헤더)>,
+    받은_블록들: HashMap<String, 블록>,
+    // 응답을 기다리는 본문 요청들. 응답이 오면 내용과 관계없이 지웁니다.
+    요청들: HashMap<RequestId, 진행중_요청>,
+}
+
+impl 동기화 {
+    pub fn 진행_중(&self) -> bool {
+        self.헤더_요청_중.is_some() || !self.헤더들.is_empty()
+    }
+
+    fn 초기화(&mut self) {
+        self.헤더_요청_중 = None;
+        self.공통_길이 = 0;
+        self.헤더들.clear();
+        self.받은_블록들.clear();
+        self.요청들.clear();
+    }
+
+    pub fn 피어_제거(&mut self, 피어: &PeerId) {
+        self.피어_팁들.remove(피어);
+        self.요청들.retain(|_, 요청| 요청.피어 != *피어);
+        if self.헤더_요청_중.as_ref() == Some(피어) {
+            self.헤더_요청_중 = None;
+        }
+    }
+
+    pub fn 팁_수신(&mut self, 피어: PeerId, 팁: 팁_정보, app: &앱) -> Vec<보낼_요청> {
+        self.피어_팁들.insert(피어, 팁);
+        self.헤더_요청_시작(app)
+    }
+
+    // 진행 중인 동기화가 없고 로칼보다 누적 작업량이 큰 팁을 알린 피어가 있으면,
+    // 그중 작업량이 가장 큰 피어에게 헤더를 요청합니다.
+    fn 헤더_요청_시작(&mut self, app: &앱) -> Vec<보낼_요청> {
+        if self.진행_중() {
+            return vec![];
+        }
+        let 로칼_작업량 = 체인_작업량(&app.블록들);
+        let 대상 = self
+            .피어_팁들
+            .iter()
+            .filter(|(_, 팁)| 팁.작업량 > 로칼_작업량)
+            .max_by_key(|(_, 팁)| 팁.작업량)
+            .map(|(피어, 팁)| (*피어, 팁.높이));
+        match 대상 {
+            Some((피어, 높이)) => {
+                info!("{} 의 팁(높이 {})이 더 무거워 헤더를 요청합니다", 피어, 높이);
+                vec![self.헤더_요청(피어, 로케이터(&app.블록들))]
+            }
+            None => vec![],
+        }
+    }
+
+    fn 헤더_요청(&mut self, 피어: PeerId, 로케이터: Vec<String>) -> 보낼_요청 {
+        self.헤더_요청_중 = Some(피어);
+        (피어, 동기화_요청::헤더들 { 로케이터 })
+    }
+
+    pub fn 헤더_수신(&mut self, 피어: PeerId, 헤더들: Vec<블록_헤더>, app: &앱) -> Vec<보낼_요청> {
+        if self.헤더_요청_중 != Some(피어) {
+            return vec![];
+        }
+        self.헤더_요청_중 = None;
+        let 받은_개수 = 헤더들.len();
+        for 헤더 in 헤더들 {
+            if let Err(사유) = self.헤더_연결(헤더, app) {
+                warn!("{} 가 보낸 헤더를 받아들이지 않음: {}", 피어, 사유);
+                self.피어_팁들.remove(&피어);
+                self.초기화();
+                return self.헤더_요청_시작(app);
+            }
+        }
+        if self.헤더들.is_empty() {
+            // 알려 준 팁이 로칼 체인 위에 있지 않았습니다. 다음에 팁을 물을 때까지 이 피어는 건너뜁니다.
+            self.피어_팁들.remove(&피어);
+            return self.헤더_요청_시작(app);
+        }
+
+        let mut 요청들 = vec![];
+        if 받은_개수 == 헤더_묶음_최대 {
+            let 마지막_해시 = self.헤더들.back().map(|(해시, _)| 해시.clone()).expect("헤더를 받았음");
+            요청들.push(self.헤더_요청(피어, vec![마지막_해시]));
+        }
+        info!("헤더 {}개를 받았습니다. 본문을 요청합니다", 받은_개수);
+        요청들.extend(self.본문_요청_배정());
+        요청들
+    }
+
+    // 헤더가 앞 헤더(또는 로칼 체인의 블록)에 이어지고 자신의 난이도 목표를 충족하는지 봅니다.
+    // 난이도 스케줄과 트랜잭션은 본문을 받아 블록으로 검증할 때 확인합니다.
+    fn 헤더_연결(&mut self, 헤더: 블록_헤더, app: &앱) -> Result<(), String> {
+        let 해시 = 헤더.해시();
+        let (이전_해시, 이전_id) = match self.헤더들.back() {
+            Some((이전_해시, 이전)) => (이전_해시.clone(), 이전.id),
+            None if 헤더.id == 0 => {
+                // 상대가 로케이터의 어떤 해시도 몰라 제네시스부터 보냈습니다.
+                self.공통_길이 = 0;
+                self.헤더들.push_back((hex::encode(해시), 헤더));
+                return Ok(());
+            }
+            None => match app.블록들.iter().position(|b| b.해시 == 헤더.이전_해시) {
+                Some(위치) => {
+                    self.공통_길이 = 위치 + 1;
+                    (app.블록들[위치].해시.clone(), app.블록들[위치].id)
+                }
+                None => return Err(format!("헤더 {} 의 이전 블록을 알지 못합니다", 헤더.id)),
+            },
+        };
+        if 헤더.이전_해시 != 이전_해시 || 헤더.id != 이전_id + 1 {
+            return Err(format!("헤더 {} 가 앞 헤더와 이어지지 않습니다", 헤더.id));
+        }
+        if !목표값::압축에서(헤더.난이도_비트).map_or(false, |목표| 목표.충족(&해시)) {
+            return Err(format!("헤더 {} 의 해시가 난이도 목표를 충족하지 않습니다", 헤더.id));
+        }
+        self.헤더들.push_back((hex::encode(해시), 헤더));
+        Ok(())
+    }
+
+    // 아직 받지도 요청하지도 않은 본문을 묶음으로 나누어, 그 높이까지 팁을 가진 피어 중
+    // 맡은 요청이 가장 적은 피어에게 보냅니다. 보낸 요청은 호출한 쪽이 요청_보냄 으로 알려 줍니다.
+    fn 본문_요청_배정(&mut self) -> Vec<보낼_요청> {
+        let 필요: Vec<(String, u64)> = {
+            let 요청된: HashSet<&String> = self.요청들.values().flat_map(|요청| 요청.해시들.iter()).collect();
+            self.헤더들
+                .iter()
+                .filter(|(해시, _)| !self.받은_블록들.contains_key(해시) && !요청된.contains(해시))
+                .map(|(해시, 헤더)| (해시.clone(), 헤더.id))
+                .collect()
+        };
+
+        let mut 요청들: Vec<보낼_요청> = vec![];
+        for 묶음 in 필요.chunks(본문_묶음_크기) {
+            let 마지막_높이 = 묶음.last().map_or(0, |(_, 높이)| *높이);
+            let 요청_수 = |피어: &PeerId| {
+                self.요청들.values().filter(|요청| &요청.피어 == 피어).count()
+                    + 요청들.iter().filter(|(대상, _)| 대상 == 피어).count()
+            };
+            let 피어 = self
+                .피어_팁들
+                .iter()
+                .filter(|(피어, 팁)| 팁.높이 >= 마지막_높이 && 요청_수(피어) < 피어당_최대_요청)
+                .min_by_key(|(피어, _)| 요청_수(피어))
+                .map(|(피어, _)| *피어);
+            let 피어 = match 피어 {
+                Some(피어) => 피어,
+                None => break,
+            };
+            let 해시들: Vec<String> = 묶음.iter().map(|(해시, _)| 해시.clone()).collect();
+            요청들.push((피어, 동기화_요청::블록들 { 해시들 }));
+        }
+        요청들
+    }
+
+    // 본문 요청을 보냈으면 응답이나 실패를 그 요청과 맞출 수 있게 기록합니다.
+    pub fn 요청_보냄(&mut self, id: RequestId, 피어: PeerId, 요청: &동기화_요청) {
+        if let 동기화_요청::블록들 { 해시들 } = 요청 {
+            self.요청들.insert(
+                id,
+                진행중_요청 {
+                    피어,
+                    해시들: 해시들.clone(),
+                },
+            );
+        }
+    }
+
+    // 받은 헤더 중 하나와 맞는 블록이면 true 입니다. 아니면 고아 블록으로 따로 처리합니다.
+    pub fn 기다리는_블록(&self, block: &블록) -> bool {
+        self.헤더들
+            .iter()
+            .any(|(해시, 헤더)| *해시 == block.해시 && *헤더 == block.헤더())
+    }
+
+    // 받은 본문을 헤더와 맞춰 보관하고 붙일 수 있는 만큼 체인에 붙입니다.
+    // 두 번째 값은 로칼 체인의 팁이 바뀌었는지입니다.
+    //
+    // 응답이 오면 요청은 끝난 것으로 봅니다. 상대가 그 사이 재구성을 겪었으면 요청한 본문 일부나
+    // 전부를 빼고 답할 수 있는데, 빠진 본문은 다시 요청할 목록으로 돌아가고 그 피어는 다음에 팁을
+    // 물을 때까지 건너뛰므로 다른 피어가 맡게 됩니다.
+    pub fn 블록_수신(
+        &mut self,
+        id: RequestId,
+        피어: PeerId,
+        블록들: Vec<블록>,
+        app: &mut 앱,
+    ) -> (Vec<보낼_요청>, bool) {
+        for block in 블록들 {
+            if self.기다리는_블록(&block) {
+                self.받은_블록들.insert(block.해시.clone(), block);
+            }
+        }
+        if let Some(요청) = self.요청들.remove(&id) {
+            let 빠진_개수 = 요청
+                .해시들
+                .iter()
+                .filter(|해시| !self.받은_블록들.contains_key(*해시))
+                .count();
+            if 빠진_개수 > 0 {
+                warn!("{} 가 요청한 본문 {}개를 보내지 않아 다른 피어에게 요청합니다", 피어, 빠진_개수);
+                self.피어_팁들.remove(&피어);
+            }
+        }
+
+        let 바뀜 = self.적용(app);
+        (self.점검(app), 바뀜)
+    }
+
+    fn 적용(&mut self, app: &mut 앱) -> bool {
+        let 분기점_그대로 = match self.헤더들.front() {
+            None => return false,
+            Some(_) if self.공통_길이 == 0 => true,
+            Some((_, 첫_헤더)) => app
+                .블록들
+                .get(self.공통_길이 - 1)
+                .map_or(false, |b| b.해시 == 첫_헤더.이전_해시),
+        };
+        if !분기점_그대로 {
+            info!("동기화 중 로칼 체인이 바뀌어 헤더부터 다시 받습니다");
+            self.초기화();
+            return false;
+        }
+
+        if self.공통_길이 > 0 && self.공통_길이 == app.블록들.len() {
+            // 로칼 체인 끝에 이어지는 경우에는 받은 순서대로 바로 붙입니다.
+            let mut 바뀜 = false;
+            loop {
+                let block = match self.헤더들.front().and_then(|(해시, _)| self.받은_블록들.get(해시)) {
+                    Some(block) => block.clone(),
+                    None => break,
+                };
+                self.받은_블록들.remove(&block.해시);
+                if !app.블록_추가시도_함수(block) {
+                    warn!("받은 블록을 체인에 붙일 수 없어 동기화를 멈춥니다");
+                    self.초기화();
+                    return 바뀜;
+                }
+                self.헤더들.pop_front();
+                self.공통_길이 += 1;
+                바뀜 = true;
+            }
+            if self.헤더들.is_empty() && self.헤더_요청_중.is_none() {
+                info!("동기화를 마쳤습니다. 높이 {}", app.블록들.len().saturating_sub(1));
+                self.초기화();
+            }
+            return 바뀜;
+        }
+
+        // 분기한 체인은 본문을 모두 받은 뒤 누적 작업량을 비교해 한 번에 교체합니다.
+        if self.헤더_요청_중.is_some() || self.헤더들.iter().any(|(해시, _)| !self.받은_블록들.contains_key(해시)) {
+            return false;
+        }
+        let 헤더들 = std::mem::take(&mut self.헤더들);
+        let mut 후보 = app.블록들[..self.공통_길이].to_vec();
+        for (해시, _) in &헤더들 {
+            후보.extend(self.받은_블록들.remove(해시));
+        }
+        self.초기화();
+        match app.체인_선택_함수(&app.블록들, 후보) {
+            Ok(선택된_체인) => match app.체인_교체_함수(선택된_체인) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("동기화한 체인을 상태에 적용할 수 없음: {}", e);
+                    false
+                }
+            },
+            Err(사유) => {
+                info!("동기화한 체인을 받아들이지 않음: {}", 사유);
+                false
+            }
+        }
+    }
+
+    // 요청이 시간 초과나 연결 끊김으로 실패했습니다. 그 피어를 빼고 다른 피어에게 다시 맡깁니다.
+    pub fn 요청_실패(&mut self, 피어: &PeerId, app: &앱) -> Vec<보낼_요청> {
+        warn!("{} 에게 보낸 동기화 요청이 실패해 다른 피어에게 요청합니다", 피어);
+        if matches!(&self.헤더_요청_중, Some(대상) if 대상 == 피어) {
+            // 이어 받던 헤더는 다른 피어가 같은 지점부터 줄 수 있다는 보장이 없으니 받아 둔 본문과
+            // 함께 버리고 헤더부터 다시 받습니다.
+            self.초기화();
+        }
+        self.피어_제거(피어);
+        self.점검(app)
+    }
+
+    // 맡을 피어가 없어 미뤄 둔 본문 요청과 새 동기화를 다시 시도합니다. 기다리는 응답도 없고
+    // 남은 본문을 맡을 피어도 없으면 동기화를 접고, 다음에 팁을 받을 때 헤더부터 다시 시작합니다.
+    pub fn 점검(&mut self, app: &앱) -> Vec<보낼_요청> {
+        let mut 요청들 = self.본문_요청_배정();
+        if 요청들.is_empty() && self.요청들.is_empty() && self.헤더_요청_중.is_none() && !self.헤더들.is_empty() {
+            info!("남은 본문을 줄 피어가 없어 동기화를 멈춥니다");
+            self.초기화();
+        }
+        요청들.extend(self.헤더_요청_시작(app));
+        요청들
+    }
+}
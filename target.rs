@@ -0,0 +1,214 @@
+use std::fmt;
+
+// 256비트 채굴 목표값입니다. 블록 해시를 빅엔디안 정수로 읽었을 때 이 값 이하이면
+// 작업증명이 성립합니다. 목표값이 작을수록 난이도가 높습니다.
+//
+// 블록에는 비트코인의 nBits 와 같은 32비트 압축 표현으로 저장합니다.
+// 상위 8비트는 바이트 단위 길이(지수), 하위 23비트는 가수이고 0x00800000 은 부호 비트입니다.
+//
+// 압축 표현 예시 (비트코인과 같은 값):
+//   0x1d00ffff -> 00000000ffff0000000000000000000000000000000000000000000000000000
+//   0x1b0404cb -> 00000000000404cb000000000000000000000000000000000000000000000000
+//   0x05009234 -> 0000000000000000000000000000000000000000000000000000000092340000
+//   0x02123456 -> 0000000000000000000000000000000000000000000000000000000000001234
+//   0x01003456 -> 0 (가수가 모두 잘려 나감)
+//   0x04923456 -> 부호 비트가 켜져 있어 None
+//   0x2201ffff -> 256비트를 넘어가므로 None
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct 목표값([u8; 32]);
+
+impl 목표값 {
+    pub fn 압축에서(비트: u32) -> Option<Self> {
+        let 지수 = (비트 >> 24) as usize;
+        let 가수 = 비트 & 0x007f_ffff;
+        if 비트 & 0x0080_0000 != 0 && 가수 != 0 {
+            return None;
+        }
+
+        let mut 바이트 = [0u8; 32];
+        let 가수_바이트 = 가수.to_be_bytes();
+        if 지수 <= 3 {
+            let 값 = 가수 >> (8 * (3 - 지수));
+            바이트[28..].copy_from_slice(&값.to_be_bytes());
+        } else {
+            // 가수 3바이트를 위에서부터 (32 - 지수) 번째 바이트에 놓습니다.
+            for (i, b) in 가수_바이트[1..].iter().enumerate() {
+                let 위치 = 32 + i;
+                if 위치 < 지수 {
+                    if *b != 0 {
+                        return None;
+                    }
+                    continue;
+                }
+                바이트[위치 - 지수] = *b;
+            }
+        }
+        Some(Self(바이트))
+    }
+
+    pub fn 압축으로(&self) -> u32 {
+        let 시작 = match self.0.iter().position(|b| *b != 0) {
+            Some(i) => i,
+            None => return 0,
+        };
+        let mut 지수 = 32 - 시작;
+        let mut 가수 = if 지수 <= 3 {
+            let mut 값 = 0u32;
+            for b in &self.0[시작..] {
+                값 = (값 << 8) | *b as u32;
+            }
+            값 << (8 * (3 - 지수))
+        } else {
+            (self.0[시작] as u32) << 16 | (self.0[시작 + 1] as u32) << 8 | self.0[시작 + 2] as u32
+        };
+        // 가수의 최상위 비트는 부호 비트와 겹치므로 한 바이트 밀어 둡니다.
+        if 가수 & 0x0080_0000 != 0 {
+            가수 >>= 8;
+            지수 += 1;
+        }
+        (지수 as u32) << 24 | 가수
+    }
+
+    pub fn 충족(&self, 해시: &[u8]) -> bool {
+        해시.len() == 32 && 해시 <= &self.0[..]
+    }
+
+    // 이 목표값을 만족하는 해시 하나를 찾는 데 기대되는 시도 횟수 2^256 / (목표값 + 1) 입니다.
+    // 상위 128비트만으로 근사하며, 목표값이 2^128 보다 작으면 u128::MAX 로 포화됩니다.
+    pub fn 작업량(&self) -> u128 {
+        let mut 상위 = [0u8; 16];
+        상위.copy_from_slice(&self.0[..16]);
+        match u128::from_be_bytes(상위).checked_add(1) {
+            Some(나누는_수) => u128::MAX / 나누는_수,
+            None => 1,
+        }
+    }
+
+    // 목표값 * 분자 / 분모 를 계산합니다. 256비트를 넘으면 가장 큰 값으로 포화됩니다.
+    pub fn 비율_조정(&self, 분자: u64, 분모: u64) -> Self {
+        let mut 조각 = self.조각들();
+
+        // 곱셈: 아래 조각부터 올림을 넘깁니다.
+        let mut 올림: u128 = 0;
+        for 값 in 조각.iter_mut().rev() {
+            let 곱 = (*값 as u128) * (분자 as u128) + 올림;
+            *값 = 곱 as u64;
+            올림 = 곱 >> 64;
+        }
+        if 올림 != 0 {
+            return Self([0xff; 32]);
+        }
+
+        // 나눗셈: 위 조각부터 나머지를 내려 보냅니다.
+        let mut 나머지: u128 = 0;
+        for 값 in 조각.iter_mut() {
+            let 현재 = (나머지 << 64) | *값 as u128;
+            *값 = (현재 / 분모 as u128) as u64;
+            나머지 = 현재 % 분모 as u128;
+        }
+        Self::조각에서(조각)
+    }
+
+    fn 조각들(&self) -> [u64; 4] {
+        let mut 조각 = [0u64; 4];
+        for (i, 값) in 조각.iter_mut().enumerate() {
+            let mut 여덟 = [0u8; 8];
+            여덟.copy_from_slice(&self.0[i * 8..i * 8 + 8]);
+            *값 = u64::from_be_bytes(여덟);
+        }
+        조각
+    }
+
+    fn 조각에서(조각: [u64; 4]) -> Self {
+        let mut 바이트 = [0u8; 32];
+        for (i, 값) in 조각.iter().enumerate() {
+            바이트[i * 8..i * 8 + 8].copy_from_slice(&값.to_be_bytes());
+        }
+        Self(바이트)
+    }
+}
+
+impl fmt::Debug for 목표값 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "목표값({})", hex::encode(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn 목표(비트: u32) -> 목표값 {
+        목표값::압축에서(비트).expect("올바른 압축 표현")
+    }
+
+    #[test]
+    fn 압축_표현_예시() {
+        let 예시 = [
+            (0x1d00ffff, "00000000ffff0000000000000000000000000000000000000000000000000000"),
+            (0x1b0404cb, "00000000000404cb000000000000000000000000000000000000000000000000"),
+            (0x05009234, "0000000000000000000000000000000000000000000000000000000092340000"),
+            (0x02123456, "0000000000000000000000000000000000000000000000000000000000001234"),
+            (0x01003456, "0000000000000000000000000000000000000000000000000000000000000000"),
+        ];
+        for (비트, 기대) in 예시.iter() {
+            assert_eq!(hex::encode(목표(*비트).0), *기대, "{:#010x}", 비트);
+        }
+    }
+
+    #[test]
+    fn 압축_왕복() {
+        for 비트 in [0x1d00ffff, 0x1b0404cb, 0x05009234, 0x207fffff, 0x03123456].iter() {
+            assert_eq!(목표(*비트).압축으로(), *비트, "{:#010x}", 비트);
+        }
+        // 잘려 나간 가수는 돌아오지 않습니다.
+        assert_eq!(목표(0x02123456).압축으로(), 0x02123400);
+        assert_eq!(목표(0x01003456).압축으로(), 0);
+    }
+
+    #[test]
+    fn 최상위_비트가_켜진_가수는_지수를_늘림() {
+        let mut 바이트 = [0u8; 32];
+        바이트[2] = 0x80;
+        assert_eq!(목표값(바이트).압축으로(), 0x1f008000);
+        assert_eq!(목표(0x1f008000), 목표값(바이트));
+    }
+
+    #[test]
+    fn 음수와_넘침은_거부() {
+        assert_eq!(목표값::압축에서(0x04923456), None);
+        assert_eq!(목표값::압축에서(0x2201ffff), None);
+        // 가수가 0 이면 부호 비트는 무시합니다.
+        assert_eq!(목표값::압축에서(0x04800000), Some(목표값([0; 32])));
+        // 넘치는 바이트가 0 이면 256비트 안에 들어갑니다.
+        assert!(목표값::압축에서(0x2100ffff).is_some());
+    }
+
+    #[test]
+    fn 충족은_목표값_이하() {
+        let 목표 = 목표(0x1d00ffff);
+        let mut 해시 = 목표.0;
+        assert!(목표.충족(&해시));
+        해시[31] = 1;
+        assert!(!목표.충족(&해시));
+        해시 = [0; 32];
+        assert!(목표.충족(&해시));
+        assert!(!목표.충족(&해시[..31]));
+    }
+
+    #[test]
+    fn 비율_조정() {
+        assert_eq!(목표(0x1d00ffff).비율_조정(2, 1).압축으로(), 0x1d01fffe);
+        assert_eq!(목표(0x1d00ffff).비율_조정(1, 4).압축으로(), 0x1c3fffc0);
+        assert_eq!(목표(0x1d00ffff).비율_조정(3, 3), 목표(0x1d00ffff));
+        // 256비트를 넘으면 가장 큰 값으로 포화됩니다.
+        assert_eq!(목표(0x2100ffff).비율_조정(u64::MAX, 1), 목표값([0xff; 32]));
+    }
+
+    #[test]
+    fn 작업량은_목표값이_작을수록_큼() {
+        assert!(목표(0x1b0404cb).작업량() > 목표(0x1d00ffff).작업량());
+        assert_eq!(목표값([0xff; 32]).작업량(), 1);
+        assert_eq!(목표값([0; 32]).작업량(), u128::MAX);
+    }
+}
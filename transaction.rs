@@ -0,0 +1,77 @@
+use libp2p::identity::ed25519;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// 서명 대상 바이트 앞에 붙여 다른 용도의 서명과 섞이지 않게 합니다.
+const 서명_도메인: &[u8] = b"chatgptblockchain/tx/v1";
+
+// 송신자가 ed25519 키로 서명한 송금 한 건입니다.
+// 송신자와 수신자는 ed25519 공개키 32바이트의 16진수 문자열(주소)입니다.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct 트랜잭션 {
+    pub 송신자: String,
+    pub 수신자: String,
+    pub 금액: u64,
+    pub 논스: u64,
+    pub 수수료: u64,
+    pub 서명: String,
+}
+
+pub fn 주소(공개키: &ed25519::PublicKey) -> String {
+    hex::encode(공개키.encode())
+}
+
+impl 트랜잭션 {
+    pub fn 서명하여_생성(
+        키: &ed25519::Keypair,
+        수신자: String,
+        금액: u64,
+        논스: u64,
+        수수료: u64,
+    ) -> Self {
+        let mut 트랜잭션 = Self {
+            송신자: 주소(&키.public()),
+            수신자,
+            금액,
+            논스,
+            수수료,
+            서명: String::new(),
+        };
+        트랜잭션.서명 = hex::encode(키.sign(&트랜잭션.서명_대상()));
+        트랜잭션
+    }
+
+    // 서명을 뺀 나머지 필드를 고정된 순서와 길이 접두사로 이어 붙인 바이트입니다.
+    pub fn 서명_대상(&self) -> Vec<u8> {
+        let mut 바이트 = 서명_도메인.to_vec();
+        for 문자열 in [&self.송신자, &self.수신자] {
+            바이트.extend_from_slice(&(문자열.len() as u32).to_be_bytes());
+            바이트.extend_from_slice(문자열.as_bytes());
+        }
+        바이트.extend_from_slice(&self.금액.to_be_bytes());
+        바이트.extend_from_slice(&self.논스.to_be_bytes());
+        바이트.extend_from_slice(&self.수수료.to_be_bytes());
+        바이트
+    }
+
+    pub fn 서명_확인(&self) -> bool {
+        let 공개키 = match hex::decode(&self.송신자).ok().and_then(|b| ed25519::PublicKey::decode(&b).ok()) {
+            Some(키) => 키,
+            None => return false,
+        };
+        let 서명 = match hex::decode(&self.서명) {
+            Ok(서명) => 서명,
+            Err(_) => return false,
+        };
+        hex::decode(&self.수신자).map_or(false, |b| b.len() == 32)
+            && 공개키.verify(&self.서명_대상(), &서명)
+    }
+
+    // 서명까지 포함한 트랜잭션 식별자입니다.
+    pub fn 해시(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.서명_대상());
+        hasher.update(self.서명.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
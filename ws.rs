@@ -0,0 +1,202 @@
+use crate::{reorg::체인_이벤트, transaction::트랜잭션};
+use libp2p::{
+    futures::{SinkExt, StreamExt},
+    PeerId,
+};
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{collections::HashSet, io, net::SocketAddr};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    select, spawn,
+    sync::broadcast::{self, error::RecvError},
+};
+use tokio_tungstenite::tungstenite::Message;
+
+// WebSocket 으로 노드 이벤트를 구독합니다. 요청과 응답은 JSON-RPC 2.0 형식입니다.
+//
+//   {"jsonrpc":"2.0","id":1,"method":"subscribe","params":["newBlocks","reorgs"]}
+//   {"jsonrpc":"2.0","id":2,"method":"unsubscribe","params":["reorgs"]}
+//
+// 구독한 종류의 이벤트는 알림으로 옵니다.
+//
+//   {"jsonrpc":"2.0","method":"subscription","params":{"subscription":"newBlocks","result":{...}}}
+//
+// 종류
+//   newBlocks  주 체인에 블록이 붙을 때. 재구성으로 새로 붙는 블록도 옵니다.
+//   reorgs     재구성 한 번마다 떼어 낸 블록과 붙인 블록의 해시들
+//   peers      찾은 피어와 인사해 같은 체인임을 확인했거나, 그 피어가 mDNS 에서 사라지거나 거부됐을 때
+//   mempool    거래 풀에 트랜잭션이 들어올 때
+
+// 느린 클라이언트를 기다리지 않고 이만큼만 쌓아 둡니다. 넘치면 그 클라이언트는 오래된 이벤트를 놓칩니다.
+pub const 방송_버퍼_크기: usize = 256;
+
+// 메인 루프와 swarm 이 방송 채널로 보내는 이벤트입니다.
+#[derive(Debug, Clone)]
+pub enum 노드_이벤트 {
+    체인(체인_이벤트),
+    피어_발견(PeerId),
+    피어_만료(PeerId),
+    거래_추가(트랜잭션),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum 구독_종류 {
+    새_블록,
+    재구성,
+    피어,
+    거래_풀,
+}
+
+impl 구독_종류 {
+    fn 이름(self) -> &'static str {
+        match self {
+            구독_종류::새_블록 => "newBlocks",
+            구독_종류::재구성 => "reorgs",
+            구독_종류::피어 => "peers",
+            구독_종류::거래_풀 => "mempool",
+        }
+    }
+
+    fn 이름에서(이름: &str) -> Option<Self> {
+        [구독_종류::새_블록, 구독_종류::재구성, 구독_종류::피어, 구독_종류::거래_풀]
+            .iter()
+            .copied()
+            .find(|종류| 종류.이름() == 이름)
+    }
+}
+
+impl 노드_이벤트 {
+    // 구독자에게 보낼 종류와 내용입니다. 해제됨은 재구성됨 요약으로 알리므로 따로 보내지 않습니다.
+    fn 알림_내용(&self) -> Option<(구독_종류, Value)> {
+        match self {
+            노드_이벤트::체인(체인_이벤트::연결됨(block)) => Some((구독_종류::새_블록, json!({ "block": block }))),
+            노드_이벤트::체인(체인_이벤트::해제됨(_)) => None,
+            노드_이벤트::체인(체인_이벤트::재구성됨 { 해제된, 연결된 }) => Some((
+                구독_종류::재구성,
+                json!({ "disconnected": 해제된, "connected": 연결된 }),
+            )),
+            노드_이벤트::피어_발견(피어) => Some((
+                구독_종류::피어,
+                json!({ "peerId": 피어.to_string(), "status": "discovered" }),
+            )),
+            노드_이벤트::피어_만료(피어) => Some((
+                구독_종류::피어,
+                json!({ "peerId": 피어.to_string(), "status": "expired" }),
+            )),
+            노드_이벤트::거래_추가(트랜잭션) => Some((
+                구독_종류::거래_풀,
+                json!({ "hash": 트랜잭션.해시(), "transaction": 트랜잭션 }),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct 구독_요청 {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Vec<String>,
+    #[serde(default)]
+    id: Value,
+}
+
+// 주소에 바로 bind 하고, 연결마다 작업을 하나씩 띄워 방송 채널을 구독하게 합니다.
+pub async fn 서버_시작(주소: SocketAddr, 방송: broadcast::Sender<노드_이벤트>) -> io::Result<()> {
+    let 리스너 = TcpListener::bind(주소).await?;
+    info!("WebSocket 구독을 ws://{} 에서 받습니다", 리스너.local_addr()?);
+    spawn(async move {
+        loop {
+            match 리스너.accept().await {
+                Ok((스트림, 상대)) => {
+                    spawn(연결_처리(스트림, 상대, 방송.subscribe()));
+                }
+                Err(e) => warn!("WebSocket 연결을 받을 수 없습니다: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn 연결_처리(스트림: TcpStream, 상대: SocketAddr, mut 수신: broadcast::Receiver<노드_이벤트>) {
+    let ws = match tokio_tungstenite::accept_async(스트림).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            info!("{} 와 WebSocket 을 열 수 없습니다: {}", 상대, e);
+            return;
+        }
+    };
+    info!("{} 가 WebSocket 으로 연결했습니다", 상대);
+    let (mut 쓰기, mut 읽기) = ws.split();
+    let mut 구독들: HashSet<구독_종류> = HashSet::new();
+
+    loop {
+        let 보낼_값 = select! {
+            메시지 = 읽기.next() => match 메시지 {
+                Some(Ok(Message::Text(텍스트))) => Some(요청_처리(&텍스트, &mut 구독들)),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // ping 에 대한 pong 은 tungstenite 가 보냅니다.
+                Some(Ok(_)) => None,
+            },
+            이벤트 = 수신.recv() => match 이벤트 {
+                Ok(이벤트) => 이벤트
+                    .알림_내용()
+                    .filter(|(종류, _)| 구독들.contains(종류))
+                    .map(|(종류, 내용)| {
+                        json!({
+                            "jsonrpc": "2.0",
+                            "method": "subscription",
+                            "params": { "subscription": 종류.이름(), "result": 내용 },
+                        })
+                    }),
+                Err(RecvError::Lagged(놓친_개수)) => {
+                    warn!("{} 가 느려 이벤트 {}개를 놓쳤습니다", 상대, 놓친_개수);
+                    Some(json!({
+                        "jsonrpc": "2.0",
+                        "method": "subscription",
+                        "params": { "subscription": "lagged", "result": { "missed": 놓친_개수 } },
+                    }))
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+        if let Some(값) = 보낼_값 {
+            if 쓰기.send(Message::Text(값.to_string())).await.is_err() {
+                break;
+            }
+        }
+    }
+    info!("{} 의 WebSocket 연결이 끝났습니다", 상대);
+}
+
+// subscribe / unsubscribe 를 처리하고 응답을 돌려줍니다. 응답의 result 는 지금 구독 중인 종류들입니다.
+fn 요청_처리(텍스트: &str, 구독들: &mut HashSet<구독_종류>) -> Value {
+    let 오류 = |id: Value, 코드: i64, 메시지: String| {
+        json!({ "jsonrpc": "2.0", "error": { "code": 코드, "message": 메시지 }, "id": id })
+    };
+    let 요청: 구독_요청 = match serde_json::from_str(텍스트) {
+        Ok(요청) => 요청,
+        Err(_) => return 오류(Value::Null, -32600, "JSON-RPC 2.0 요청이 아닙니다".to_string()),
+    };
+    if 요청.jsonrpc != "2.0" {
+        return 오류(요청.id, -32600, "JSON-RPC 2.0 요청이 아닙니다".to_string());
+    }
+    let mut 종류들 = vec![];
+    for 이름 in &요청.params {
+        match 구독_종류::이름에서(이름) {
+            Some(종류) => 종류들.push(종류),
+            None => return 오류(요청.id, -32602, format!("구독 종류 {} 가 없습니다", 이름)),
+        }
+    }
+    match 요청.method.as_str() {
+        "subscribe" => 구독들.extend(종류들),
+        "unsubscribe" => 종류들.iter().for_each(|종류| {
+            구독들.remove(종류);
+        }),
+        메서드 => return 오류(요청.id, -32601, format!("메서드 {} 가 없습니다", 메서드)),
+    }
+    let 구독_중: Vec<&str> = 구독들.iter().map(|종류| 종류.이름()).collect();
+    json!({ "jsonrpc": "2.0", "result": 구독_중, "id": 요청.id })
+}